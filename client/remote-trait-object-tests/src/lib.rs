@@ -1,10 +0,0 @@
-#[macro_use]
-extern crate log;
-
-#[cfg(test)]
-mod ping;
-mod simple;
-mod test_store;
-pub mod transport;
-
-pub use test_store::{massive_no_export, massive_with_export};
@@ -3,17 +3,24 @@ use rustls::{client, server};
 use core::num;
 use std::error::Error;
 use std::net::SocketAddr;
+use std::path::Path;
 use remote_trait_object::{Context, Service, Config, ServiceToImport};
 use std::sync::Arc;
 use std::time::Duration;
 use std::panic::AssertUnwindSafe;
 
-mod transport;
-mod image_steganographer;
-mod quinn_utils;
-use image_steganographer::{ImageSteganographer, SomeImageSteganographer};
-use transport::{create, TransportEnds};
-use quinn_utils::*;
+use common::image_steganographer::{EncodeOptions, ImageSteganographer, SomeImageSteganographer};
+use common::container::HiddenFile;
+use common::quality::QualityReport;
+use common::crypto::Encryption;
+use common::access::AccessRules;
+use common::transport::{connect, TransportEnds};
+use common::quinn_utils::*;
+use common::mux::ALPN_STEG;
+use common::carriers::{Carrier, CarrierLibrary};
+use common::error::StegError;
+use common::stego::{EmbedKey, Mode};
+use common::animation;
 use quinn_proto::crypto::rustls::QuicClientConfig;
 use image;
 use steganography::{self, util::file_to_bytes};
@@ -25,6 +32,193 @@ use std::fs::OpenOptions;
 use std::io::Write;
 use tokio::sync::Semaphore;
 
+// Carrier to hide the secrets in, from `P2P_CARRIER`: `id:<id>` or `tag:<tag>` pick one from
+// the server's library, anything else is the path of a local image to upload. Unset lets the
+// server choose.
+fn carrier_from_env() -> Result<Carrier, String> {
+    let spec = match env::var("P2P_CARRIER") {
+        Ok(spec) => spec,
+        Err(_) => return Ok(Carrier::Default),
+    };
+    if let Some(id) = spec.strip_prefix("id:") {
+        Ok(Carrier::Library(id.to_string()))
+    } else if let Some(tag) = spec.strip_prefix("tag:") {
+        Ok(Carrier::Tagged(tag.to_string()))
+    } else {
+        std::fs::read(&spec).map(Carrier::Image).map_err(|e| format!("{}: {}", spec, e))
+    }
+}
+
+// Secrets are sealed before hiding when `P2P_PAYLOAD_PASSWORD` is set. `P2P_VIEWERS`, a comma
+// separated list of users, limits who may view them to `P2P_VIEWS` views (default 1).
+// `P2P_MODE=dct` hides them so they survive JPEG recompression, `P2P_MODE=scattered` spreads
+// them over pixels picked by the key in `P2P_EMBED_KEY`.
+fn encode_options_from_env() -> Result<EncodeOptions, String> {
+    let access = match env::var("P2P_VIEWERS") {
+        Ok(viewers) => Some(AccessRules {
+            viewers: viewers.split(',').map(str::trim).filter(|v| !v.is_empty()).map(String::from).collect(),
+            views: match env::var("P2P_VIEWS") {
+                Ok(views) => views.parse().map_err(|e| format!("P2P_VIEWS: {}", e))?,
+                Err(_) => 1,
+            },
+            expires_at: None,
+        }),
+        Err(_) => None,
+    };
+    let mode = match env::var("P2P_MODE").as_deref() {
+        Err(_) | Ok("lsb") => Mode::Lsb,
+        Ok("dct") => Mode::Dct,
+        Ok("scattered") => Mode::Scattered,
+        Ok(other) => return Err(format!("P2P_MODE: unknown mode {:?}, expected lsb, dct or scattered", other)),
+    };
+    let embed_key = env::var("P2P_EMBED_KEY").ok().map(EmbedKey);
+    if (mode == Mode::Scattered) != embed_key.is_some() {
+        return Err("P2P_EMBED_KEY must be set for P2P_MODE=scattered and only then".to_string());
+    }
+    let redundancy = match env::var("P2P_REDUNDANCY") {
+        Ok(redundancy) => Some(redundancy.parse().map_err(|e| format!("P2P_REDUNDANCY: {}", e))?),
+        Err(_) => None,
+    };
+    Ok(EncodeOptions {
+        encryption: env::var("P2P_PAYLOAD_PASSWORD").ok().map(Encryption::Password),
+        access,
+        mode,
+        embed_key,
+        redundancy,
+        ..Default::default()
+    })
+}
+
+// How one attempt at hiding a secret on one server ended
+enum Attempt {
+    Done,
+    Retry,
+    GiveUp,
+}
+
+// What to tell the user about a failed call, and whether another attempt, possibly on
+// another server, can succeed.
+fn triage(error: &StegError) -> (String, bool) {
+    match error {
+        StegError::InvalidImage(e) => (format!("the image could not be read ({})", e), false),
+        StegError::InvalidRequest(e) => (e.clone(), false),
+        StegError::PayloadTooLarge { payload, capacity } => {
+            (format!("{} bytes do not fit, the carrier holds {} bytes", payload, capacity), false)
+        }
+        StegError::NoPayload => ("the image does not hide anything".to_string(), false),
+        StegError::CorruptPayload(e) => (format!("the hidden payload is damaged ({})", e), false),
+        StegError::UnsupportedVersion(v) => (format!("the payload needs a newer client (container version {})", v), false),
+        StegError::CarrierUnavailable(e) => (format!("{}, another server may have it", e), true),
+        StegError::EmbedFailed(e) => (format!("{}, try another carrier or mode", e), false),
+        StegError::KeyRequired => ("the payload is encrypted, set P2P_PAYLOAD_PASSWORD".to_string(), false),
+        StegError::WrongKey => ("P2P_PAYLOAD_PASSWORD does not match the payload".to_string(), false),
+        StegError::Unauthorized(e) => (format!("the server refused: {}", e), false),
+        StegError::AccessDenied(e) => (e.clone(), false),
+        StegError::Unsupported(e) => (format!("{}, another server may support it", e), true),
+        StegError::Io(e) => (format!("I/O error on the server ({})", e), true),
+        StegError::Internal(e) => (format!("server error ({})", e), true),
+        StegError::InvalidAudio(e) => (format!("the audio could not be read ({})", e), false),
+    }
+}
+
+// The files directly inside `dir`, by name. Their MIME types are left to the server.
+fn read_folder(dir: &Path) -> Result<Vec<HiddenFile>, String> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = path.file_name().and_then(|n| n.to_str()).ok_or_else(|| format!("{}: bad file name", path.display()))?;
+        let content = std::fs::read(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        files.push(HiddenFile { name: name.to_string(), content, ..Default::default() });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+// Animated GIF carriers come back as GIFs, the others as PNG or as JPEG in DCT mode
+fn stego_extension(carrier: &Carrier, options: &EncodeOptions) -> &'static str {
+    match carrier {
+        Carrier::Image(bytes) if animation::is_gif(bytes) => "gif",
+        _ if options.mode == Mode::Dct => "jpg",
+        _ => "png",
+    }
+}
+
+// Hides all files of a folder as one bundle, on the first server that takes it, and saves
+// the stego images as encoded_images/stego_<folder>_<part>.<ext>
+async fn hide_folder(
+    client_endpoint: Endpoint,
+    server_addrs: &[SocketAddr],
+    dir: &Path,
+    carrier: &Carrier,
+    options: &EncodeOptions,
+) -> Result<(), String> {
+    let folder_name = dir.file_name().and_then(|n| n.to_str()).unwrap_or("folder").to_string();
+    let files = read_folder(dir)?;
+    let extension = stego_extension(carrier, options);
+    for &addr in server_addrs {
+        let ends = match timeout(Duration::from_secs(10), connect(client_endpoint.clone(), addr)).await {
+            Ok(Ok(ends)) => ends,
+            _ => {
+                println!("Could not reach {} for folder {}", addr, folder_name);
+                continue;
+            }
+        };
+        let (context_user, image_steganographer): (Context, ServiceToImport<dyn ImageSteganographer>) =
+            Context::with_initial_service_import(Config::default_setup(), ends.send.clone(), ends.recv.clone());
+        context_user.disable_garbage_collection();
+        let image_steganographer_proxy: Box<dyn ImageSteganographer> = image_steganographer.into_proxy();
+
+        match image_steganographer_proxy.encode_files(&files, &[carrier.clone()], options) {
+            Ok(stego_images) => {
+                std::fs::create_dir_all("encoded_images").map_err(|e| e.to_string())?;
+                for (part, stego_image) in stego_images.iter().enumerate() {
+                    let stego_path = format!("encoded_images/stego_{}_{}.{}", folder_name, part, extension);
+                    std::fs::write(&stego_path, stego_image).map_err(|e| format!("{}: {}", stego_path, e))?;
+                }
+                println!("Folder {} ({} files) hidden in {} images", folder_name, files.len(), stego_images.len());
+                return Ok(());
+            }
+            Err(e) => {
+                let (message, retry) = triage(&e);
+                println!("Error hiding folder {}: {}", folder_name, message);
+                if !retry {
+                    return Err(message);
+                }
+            }
+        }
+    }
+    Err(format!("no server could hide folder {}", folder_name))
+}
+
+// Stego images worse than `P2P_MIN_PSNR` (dB) or changing a channel by more than
+// `P2P_MAX_DELTA` are rejected. Every report is appended to quality_report.csv.
+fn check_quality(file_name: &str, report: &QualityReport) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open("quality_report.csv")
+        .map_err(|e| format!("Failed to open CSV file: {}", e))?;
+    writeln!(file, "{},{:.2},{:.4},{},{},{},{},{},{:.3}",
+        file_name, report.psnr, report.ssim, report.max_delta, report.modified_pixels,
+        report.total_pixels, report.payload, report.capacity, report.fill_ratio())
+        .map_err(|e| format!("Failed to write to CSV file: {}", e))?;
+
+    if let Some(min_psnr) = env::var("P2P_MIN_PSNR").ok().and_then(|v| v.parse::<f64>().ok()) {
+        if report.psnr < min_psnr {
+            return Err(format!("PSNR of {:.2} dB is below the required {} dB", report.psnr, min_psnr));
+        }
+    }
+    if let Some(max_delta) = env::var("P2P_MAX_DELTA").ok().and_then(|v| v.parse::<u8>().ok()) {
+        if report.max_delta > max_delta {
+            return Err(format!("pixels changed by up to {}, more than the allowed {}", report.max_delta, max_delta));
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     
@@ -42,12 +236,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     //let (server_endpoint, _server_cert) = make_server_endpoint(server_addr).unwrap();
     
 
-    let mut client_config = ClientConfig::new(Arc::new(QuicClientConfig::try_from(
-        rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(SkipServerVerification::new())
-            .with_no_client_auth(),
-    )?));
+    let tls = TlsConfig::client_from_env();
+    let mut client_config = configure_client_tls(&tls, ALPN_STEG).map_err(|e| e.to_string())?;
     let mut transport_config = TransportConfig::default();
     transport_config.keep_alive_interval(Some(Duration::from_secs(5)));
     client_config.transport_config(Arc::new(transport_config));
@@ -62,11 +252,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
     println!("Creating transport ends.");
 
 
-    // Load all secret images from the secret_images folder
+    let carrier = Arc::new(carrier_from_env()?);
+    let options = Arc::new(encode_options_from_env()?);
+
+    // Load all secret images from the secret_images folder. Folders in it are hidden whole,
+    // each as one bundle.
     let secret_images_path = "secret_images";
     let secret_images = std::fs::read_dir(secret_images_path).map_err(|e| e.to_string())?
         .filter_map(Result::ok)
-        .filter(|entry| entry.path().is_file())
+        .filter(|entry| entry.path().is_file() || entry.path().is_dir())
         .map(|entry| entry.path())
         .collect::<Vec<_>>();
 
@@ -91,6 +285,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         let server_addrs = server_addrs.clone();
         let client_endpoint = client_endpoint.clone();
         let semaphore = semaphore.clone();
+        let carrier = carrier.clone();
+        let options = options.clone();
 
         let stego_portion = tokio::spawn(async move {
             for (index, entry) in secret_images.iter().enumerate() {
@@ -102,6 +298,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .to_string();
         
             println!("Processing secret image {}: {}", index, secret_file_name);
+
+            if secret_path.is_dir() {
+                if let Err(e) = hide_folder(client_endpoint.clone(), &server_addrs, secret_path, &carrier, &options).await {
+                    println!("Giving up on folder {}: {}", secret_file_name, e);
+                }
+                continue;
+            }
         
             let secret_file = std::fs::File::open(&secret_path)
                 .map_err(|e| format!("Failed to open secret file: {}", e))?;
@@ -109,17 +312,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
             let secret_image_bytes = &secret_image;
         
             // Generate unique output paths for each image
-            let stego_path = format!("encoded_images/stego_{}.png", secret_file_name);
+            let extension = stego_extension(&carrier, &options);
+            let stego_path = format!("encoded_images/stego_{}.{}", secret_file_name, extension);
             let finale_path = format!("decoded_images");
         
+            // An uploaded carrier can be checked before talking to any server
+            if let Carrier::Image(_) = *carrier {
+                let report = CarrierLibrary::default()
+                    .check_fit(&carrier, secret_image_bytes.len() + options.overhead(), options.mode)
+                    .map_err(|e| e.to_string())?;
+                if !report.fits {
+                    println!("Skipping {}: {} bytes do not fit into the carrier, which holds {} bytes",
+                        secret_file_name, report.payload, report.capacity());
+                    continue;
+                }
+            }
+
             println!("Encoding secret image {}...", index);
             let start_time = std::time::Instant::now();
             let mut success = false;
+            let mut give_up = false;
             
             let mut retries = 0;
             let max_retries = 3;
             let mut backoff_duration = Duration::from_secs(2);
-            while retries < max_retries && !success {
+            while retries < max_retries && !success && !give_up {
                 let mut handles = vec![];
         
                 for addr in server_addrs.clone() {
@@ -131,22 +348,20 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     let finale_path = finale_path.clone();
                     let secret_file_name = secret_file_name.clone();
                     let semaphore = semaphore.clone();
+                    let carrier = carrier.clone();
+                    let options = options.clone();
             
                     let handle = tokio::spawn(async move {
                         let permit = semaphore.acquire().await.unwrap(); // Acquire a permit
-                        let ends = match timeout(Duration::from_secs(10), create(client_endpoint.clone(), addr)).await {
+                        let ends = match timeout(Duration::from_secs(10), connect(client_endpoint.clone(), addr)).await {
                             Ok(Ok(ends)) => ends,
                             Ok(Err(e)) => {
-                                retries += 1;
-                                backoff_duration *= 2;
                                 println!("Error creating transport ends: {}", e);
-                                return;
+                                return Attempt::Retry;
                             }
                             Err(_) => {
-                                retries += 1;
-                                backoff_duration *= 2;
                                 println!("Timeout occurred while creating transport ends");
-                                return;
+                                return Attempt::Retry;
                             }
                         };
                         
@@ -154,34 +369,82 @@ async fn main() -> Result<(), Box<dyn Error>> {
                             Context::with_initial_service_import(Config::default_setup(), ends.send.clone(), ends.recv.clone());
                         context_user.disable_garbage_collection();
                         let image_steganographer_proxy: Box<dyn ImageSteganographer> = image_steganographer.into_proxy();
+
+                        // Dry run first: a secret that fits nowhere is not worth retrying, and if the
+                        // requested carrier is too small the server's own pick may still do
+                        let secret_len = secret_image_bytes.len() as u64;
+                        let mut carrier = (*carrier).clone();
+                        match image_steganographer_proxy.check_fit_with(secret_len, &carrier, &options) {
+                            Ok(report) if report.fits => {
+                                println!("Secret image {} fits with {} bytes to spare", index, report.headroom());
+                            }
+                            Ok(report) => {
+                                let fallback = if carrier != Carrier::Default {
+                                    image_steganographer_proxy.check_fit_with(secret_len, &Carrier::Default, &options).ok().filter(|r| r.fits)
+                                } else {
+                                    None
+                                };
+                                match fallback {
+                                    Some(fallback) => {
+                                        println!("Carrier too small for secret image {}, using server carrier {:?} instead",
+                                            index, fallback.carrier.id);
+                                        carrier = Carrier::Default;
+                                    }
+                                    None => {
+                                        println!("Secret image {} ({} bytes) does not fit, the best carrier holds {} bytes",
+                                            index, report.payload, report.capacity());
+                                        drop(permit);
+                                        return Attempt::GiveUp;
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                let (message, retry) = triage(&e);
+                                println!("Capacity check failed: {}", message);
+                                drop(permit);
+                                return if retry { Attempt::Retry } else { Attempt::GiveUp };
+                            }
+                        }
+
                         println!("Encoding secret image {} with proxy", index);
                         let stegano = timeout(Duration::from_secs(60), async {
                             std::panic::catch_unwind(AssertUnwindSafe(|| {
-                                match image_steganographer_proxy.encode(&secret_image_bytes, &stego_path, &secret_file_name) {
-                                    Ok(encoded_bytes) => Ok(encoded_bytes),
-                                    Err(e) => {
-                                        retries += 1;
-                                        backoff_duration *= 2;
-                                        println!("Error during encoding: {:?}", e);
-                                        Err(e)
-                                    }
-                                }
+                                image_steganographer_proxy.encode_with_report(&secret_image_bytes, &secret_file_name, &carrier, &options)
                             }))
                         }).await.unwrap_or_else(|_| Err(Box::new("Timeout occurred during encoding".to_string()) as Box<dyn std::any::Any + std::marker::Send>));
                         
                         // Handle the result
-                        match stegano {
-                            Ok(_) => {
-                                println!("Encoding completed successfully")
-                                
+                        let attempt = match stegano {
+                            Ok(Ok(encoded)) => {
+                                // The server only returns the stego image, storing it is up to us
+                                if let Err(e) = check_quality(&secret_file_name, &encoded.quality) {
+                                    println!("Rejecting stego image for {}: {}", secret_file_name, e);
+                                    Attempt::GiveUp
+                                } else {
+                                    match std::fs::create_dir_all("encoded_images").and_then(|_| std::fs::write(&stego_path, &encoded.stego_image)) {
+                                        Ok(()) => {
+                                            println!("Encoding completed successfully, saved to {} (PSNR {:.2} dB)", stego_path, encoded.quality.psnr);
+                                            Attempt::Done
+                                        }
+                                        Err(e) => {
+                                            println!("Failed to save {}: {}", stego_path, e);
+                                            Attempt::GiveUp
+                                        }
+                                    }
+                                }
+                            },
+                            Ok(Err(e)) => {
+                                let (message, retry) = triage(&e);
+                                println!("Error during encoding of secret image {}: {}", index, message);
+                                if retry { Attempt::Retry } else { Attempt::GiveUp }
                             },
                             Err(e) => {
-                                retries += 1;
-                                backoff_duration *= 2;
                                 println!("Failed to encode: {:?}", e);
+                                Attempt::Retry
                             }
-                        }
+                        };
                         drop(permit); // Release the permit
+                        attempt
                     });
                     handles.push(handle);
                 }
@@ -189,16 +452,28 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 let results: Vec<_> = futures::future::join_all(handles).await;
                 for result in results {
                     match result {
-                        Ok(_) => {
+                        Ok(Attempt::GiveUp) => {
+                            give_up = true;
+                        },
+                        Ok(Attempt::Done) => {
                             println!("Secret image {} processed successfully", index);
                             success = true;
                         },
+                        Ok(Attempt::Retry) => {},
                         Err(e) => {
                             println!("Error in task: {}", e);
                         }
                     }
                 }
-                tokio::time::sleep(backoff_duration).await;
+                if give_up {
+                    println!("Giving up on secret image {}", index);
+                    break;
+                }
+                if !success {
+                    retries += 1;
+                    tokio::time::sleep(backoff_duration).await;
+                    backoff_duration *= 2;
+                }
             }
             
             }
@@ -270,7 +545,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     //             let mut handles = vec![];
 
     //             for addr in server_addrs.clone() {
-    //                 let ends = create(client_endpoint.clone(),addr).await?;
+    //                 let ends = connect(client_endpoint.clone(),addr).await?;
 
     //                 let secret_image_bytes = secret_image_bytes.clone();
     //                 let stego_path = stego_path.clone();
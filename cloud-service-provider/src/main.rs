@@ -2,10 +2,10 @@ use crossbeam::channel::{unbounded, Receiver, Sender};
 //use std::collections::HashMap;
 use std::{env, thread};
 
-mod image_steganographer;
 use std::fs::File;
-use image_steganographer::ImageSteganographer;
-use image_steganographer::SomeImageSteganographer; // Add this line to import the trait
+use std::path::Path;
+use common::image_steganographer::{DecodeOptions, ImageSteganographer, SomeImageSteganographer};
+use common::carriers::Carrier;
 use std::io::{Read, Write};
 use std::sync::{Arc, Barrier};
 use remote_trait_object::{Context, Config, ServiceToExport, ServiceToImport};
@@ -30,12 +30,10 @@ fn main() {
 
     let secret_path = format!("{}/secret.jpg", file_path);
     let output_path1 = format!("{}/stego.png", file_path);
-    let output_path2 = format!("{}/output.png", file_path);
 
     let carrier_path: &str = &format!("{}/carrier.jpg", file_path);
     let secret_path: &str = &format!("{}/secret.jpg", file_path);
     let output_path1: &str = &format!("{}/stego.png", file_path);
-    let output_path2: &str = &format!("{}/output.png", file_path);
     //let image = steganographer.encode(&secret_path, &carrier_path, &output_path1).unwrap();
     
     
@@ -63,11 +61,17 @@ fn main() {
     let image_steganographer_proxy: Box<dyn ImageSteganographer> = image_steganographer.into_proxy();
 
     // Test the encode method
-    image_steganographer_proxy.encode(secret_path.clone(), carrier_path.clone(), output_path1.clone()).unwrap();
+    let secret_image = std::fs::read(secret_path).unwrap();
+    let carrier = Carrier::Image(std::fs::read(carrier_path).unwrap());
+    let encoded_image = image_steganographer_proxy.encode(&secret_image, "secret.jpg", &carrier).unwrap();
+    std::fs::write(output_path1, &encoded_image).unwrap();
     println!("Encode method invoked successfully.");
 
     // Test the decode method
-    image_steganographer_proxy.decode(output_path1.clone(), output_path2.clone()).unwrap();
+    // The secret comes back byte for byte, under the name it was encoded with. That name comes
+    // from the image, so it is only ever written inside the output directory
+    let decoded_secret = image_steganographer_proxy.decode_file(&encoded_image, &DecodeOptions::default()).unwrap();
+    decoded_secret.save_in(&Path::new(file_path).join("decoded")).unwrap();
     println!("Decode method invoked successfully.");
 
     /*
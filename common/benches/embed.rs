@@ -0,0 +1,99 @@
+//! Throughput of embedding and extraction the way every call used to run, sequentially on the
+//! calling thread, against the parallel code on one thread and on the whole rayon pool.
+//!
+//! ```text
+//! cargo bench -p common --bench embed
+//! P2P_BENCH_MEGAPIXELS=20 cargo bench -p common --bench embed
+//! ```
+//!
+//! Payloads fill half of the carrier's capacity in each mode, and throughput is given in
+//! megapixels of carrier per second. Encoding to PNG or JPEG is not included; it is the same
+//! on both paths.
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use common::dct::{self, DctParams};
+use common::stego;
+use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+use rayon::ThreadPool;
+
+const RUNS: usize = 3;
+
+// A smooth gradient, so DCT embedding stays within a small pixel difference
+fn carrier(megapixels: f64) -> DynamicImage {
+    let width = (megapixels * 1e6 * 4.0 / 3.0).sqrt() as u32 / 8 * 8;
+    let height = width * 3 / 4;
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+        Rgba([(x * 255 / width) as u8, (y * 255 / height) as u8, ((x + y) % 97 + 80) as u8, 255])
+    }))
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+// Fastest of a few runs of `job`
+fn fastest<T>(job: impl Fn() -> T) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            job();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// Times `sequential` on this thread and `parallel` on each of `pools`
+fn compare<T: Send>(
+    name: &str,
+    megapixels: f64,
+    pools: &[ThreadPool; 2],
+    sequential: impl Fn() -> T,
+    parallel: impl Fn() -> T + Send + Sync,
+) {
+    let baseline = fastest(sequential);
+    let [single, all] = pools.each_ref().map(|pool| fastest(|| pool.install(&parallel)));
+    let column = |time: Duration| {
+        format!("{:>8.1} ms {:>7.1} MP/s", time.as_secs_f64() * 1e3, megapixels / time.as_secs_f64())
+    };
+    println!(
+        "{:<12} sequential {}   1 thread {}   {} threads {}   {:>5.2}x",
+        name,
+        column(baseline),
+        column(single),
+        pools[1].current_num_threads(),
+        column(all),
+        baseline.as_secs_f64() / all.as_secs_f64(),
+    );
+}
+
+fn main() {
+    let megapixels = env::var("P2P_BENCH_MEGAPIXELS").ok().and_then(|v| v.parse().ok()).unwrap_or(8.0);
+    let pool = |threads| rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+    let pools = [pool(1), pool(0)];
+    let carrier = carrier(megapixels);
+    let (width, height) = carrier.dimensions();
+    let megapixels = f64::from(width * height) / 1e6;
+    println!("{}x{} carrier, best of {} runs", width, height, RUNS);
+
+    let secret = payload(stego::capacity(width, height) / 2);
+    compare("lsb embed", megapixels, &pools,
+        || stego::embed_sequential(&carrier, &secret).unwrap(),
+        || stego::embed(&carrier, &secret).unwrap());
+    let stego_image = DynamicImage::ImageRgba8(stego::embed(&carrier, &secret).unwrap());
+    compare("lsb extract", megapixels, &pools,
+        || stego::extract_sequential(&stego_image).unwrap(),
+        || stego::extract(&stego_image).unwrap());
+
+    let params = DctParams { quality: 75, max_pixel_diff: u8::MAX };
+    let secret = payload(dct::capacity(width, height) / 2);
+    compare("dct embed", megapixels, &pools,
+        || dct::embed_sequential(&carrier, &secret, &params).unwrap(),
+        || dct::embed(&carrier, &secret, &params).unwrap());
+    let stego_image = DynamicImage::ImageRgba8(dct::embed(&carrier, &secret, &params).unwrap());
+    compare("dct extract", megapixels, &pools,
+        || dct::extract_sequential(&stego_image, &params).unwrap(),
+        || dct::extract(&stego_image, &params).unwrap());
+}
@@ -0,0 +1,312 @@
+//! Usage-controlled secrets: who may view a hidden image, how often and until when.
+//!
+//! The owner attaches [`AccessRules`] when encoding. The node turns them into an
+//! [`AccessPolicy`], signs it with the cluster key and embeds it next to the secret, which is
+//! sealed with a key only the cluster knows, so the bits alone reveal nothing. Viewing goes
+//! through a node, which checks the viewer, re-embeds the re-signed policy with one view
+//! less and only then takes the view off the count.
+//!
+//! ```text
+//! P2PA | policy length (u32 BE) | policy (bincode) | signature (64) | sealed secret
+//! ```
+//!
+//! The count lives in the image. A viewer who kept an older copy could present it again, so
+//! nodes remember the lowest count they have seen for each policy and refuse stale copies.
+//! Like quota usage, that memory starts over when the node restarts.
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::certs::{self, ClusterKey};
+use crate::crypto::{self, Encryption};
+use crate::error::StegError;
+use crate::quinn_utils::TlsConfig;
+
+const MAGIC: &[u8; 4] = b"P2PA";
+const LEN_BYTES: usize = 4;
+const SIGNATURE_LEN: usize = 64;
+const SEAL_PURPOSE: &str = "p2p-access-seal/1";
+
+/// What the owner asks for when encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessRules {
+    /// Users other than the owner who may view the secret.
+    pub viewers: Vec<String>,
+    /// Views granted across all viewers.
+    pub views: u32,
+    /// Seconds since the Unix epoch after which viewers are refused.
+    pub expires_at: Option<u64>,
+}
+
+/// The policy embedded in an image. The owner is always the authenticated encoding user.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessPolicy {
+    pub id: [u8; 16],
+    pub owner: String,
+    pub viewers: Vec<String>,
+    pub remaining_views: u32,
+    pub expires_at: Option<u64>,
+}
+
+impl AccessPolicy {
+    pub fn new(owner: &str, rules: &AccessRules) -> Self {
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+        Self {
+            id,
+            owner: owner.to_string(),
+            viewers: rules.viewers.clone(),
+            remaining_views: rules.views,
+            expires_at: rules.expires_at,
+        }
+    }
+
+    /// Whether `viewer` may see the secret at `now`. The owner always may.
+    pub fn check(&self, viewer: &str, now: u64) -> Result<(), StegError> {
+        if viewer == self.owner {
+            return Ok(());
+        }
+        if !self.viewers.iter().any(|v| v == viewer) {
+            return Err(StegError::AccessDenied(format!("{} is not allowed to view this image", viewer)));
+        }
+        if self.expires_at.is_some_and(|expires_at| now >= expires_at) {
+            return Err(StegError::AccessDenied("access to this image has expired".to_string()));
+        }
+        if self.remaining_views == 0 {
+            return Err(StegError::AccessDenied("no views of this image are left".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Result of a successful [`AccessControl::view`].
+pub struct View {
+    pub secret: Vec<u8>,
+    /// The payload to embed again, carrying the updated policy.
+    pub payload: Vec<u8>,
+    pub policy: AccessPolicy,
+}
+
+pub fn is_protected(payload: &[u8]) -> bool {
+    payload.starts_with(MAGIC)
+}
+
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Signs, checks and counts down access policies with the cluster key.
+pub struct AccessControl {
+    key: ClusterKey,
+    // Lowest remaining view count seen per policy id
+    seen: Mutex<HashMap<[u8; 16], u32>>,
+}
+
+impl AccessControl {
+    pub fn new(key: ClusterKey) -> Self {
+        Self { key, seen: Mutex::new(HashMap::new()) }
+    }
+
+    /// Reads the cluster key from `P2P_CLUSTER_KEY` (default `cluster.key` in `P2P_CERT_DIR`).
+    /// A cluster node without the key cannot handle access policies and gets `None`; outside
+    /// of [`TlsConfig::Cluster`] a key is created on first use.
+    pub fn from_env(tls: &TlsConfig) -> Result<Option<Self>, Box<dyn Error + Send + Sync + 'static>> {
+        let path = env::var("P2P_CLUSTER_KEY").map(PathBuf::from).unwrap_or_else(|_| {
+            PathBuf::from(env::var("P2P_CERT_DIR").unwrap_or_else(|_| "certs".to_string())).join(certs::CLUSTER_KEY_FILE)
+        });
+        let key = match tls {
+            TlsConfig::Cluster { .. } if !path.exists() => return Ok(None),
+            TlsConfig::Cluster { .. } => ClusterKey::load(&path)?,
+            _ => ClusterKey::load_or_create(&path)?,
+        };
+        Ok(Some(Self::new(key)))
+    }
+
+    /// The cluster key, which the node also signs provenance with.
+    pub fn key(&self) -> &ClusterKey {
+        &self.key
+    }
+
+    fn seal_key(&self) -> Encryption {
+        Encryption::Key(self.key.derive(SEAL_PURPOSE))
+    }
+
+    fn signed_message(policy: &[u8], sealed: &[u8]) -> Vec<u8> {
+        let mut message = MAGIC.to_vec();
+        message.extend_from_slice(policy);
+        message.extend_from_slice(&Sha256::digest(sealed));
+        message
+    }
+
+    /// Bytes `policy` adds on top of the secret.
+    pub fn overhead(&self, policy: &AccessPolicy) -> usize {
+        let policy_len = bincode::serialized_size(policy).unwrap_or_default() as usize;
+        MAGIC.len() + LEN_BYTES + policy_len + SIGNATURE_LEN + crypto::overhead(&self.seal_key())
+    }
+
+    fn assemble(&self, policy: &AccessPolicy, sealed: &[u8]) -> Result<Vec<u8>, StegError> {
+        let policy = bincode::serialize(policy).map_err(|e| StegError::Internal(e.to_string()))?;
+        let signature = self.key.sign(&Self::signed_message(&policy, sealed));
+        let mut payload = MAGIC.to_vec();
+        payload.extend_from_slice(&(policy.len() as u32).to_be_bytes());
+        payload.extend_from_slice(&policy);
+        payload.extend_from_slice(&signature);
+        payload.extend_from_slice(sealed);
+        Ok(payload)
+    }
+
+    /// Seals `secret` and puts the signed `policy` in front of it.
+    pub fn protect(&self, policy: &AccessPolicy, secret: &[u8]) -> Result<Vec<u8>, StegError> {
+        let sealed = crypto::seal(secret, &self.seal_key())?;
+        self.assemble(policy, &sealed)
+    }
+
+    /// Reads and verifies the policy of a protected payload.
+    pub fn policy<'a>(&self, payload: &'a [u8]) -> Result<(AccessPolicy, &'a [u8]), StegError> {
+        if !is_protected(payload) {
+            return Err(StegError::InvalidRequest("image has no access policy".to_string()));
+        }
+        let rest = &payload[MAGIC.len()..];
+        if rest.len() < LEN_BYTES {
+            return Err(StegError::CorruptPayload("access policy is truncated".to_string()));
+        }
+        let (len, rest) = rest.split_at(LEN_BYTES);
+        let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+        if rest.len() < len + SIGNATURE_LEN {
+            return Err(StegError::CorruptPayload("access policy is truncated".to_string()));
+        }
+        let (policy, rest) = rest.split_at(len);
+        let (signature, sealed) = rest.split_at(SIGNATURE_LEN);
+        let signature: &[u8; SIGNATURE_LEN] = signature.try_into().unwrap();
+        if !certs::verify_signature(&self.key.verifying_key(), &Self::signed_message(policy, sealed), signature) {
+            return Err(StegError::CorruptPayload("access policy signature is invalid".to_string()));
+        }
+        let policy = bincode::deserialize(policy).map_err(|e| StegError::CorruptPayload(format!("access policy is malformed: {}", e)))?;
+        Ok((policy, sealed))
+    }
+
+    /// Reveals the secret to `viewer` if the policy allows it. Views by anyone but the owner
+    /// are counted, and the returned payload carries the decremented count. Nothing is used up
+    /// until the view is passed to [`AccessControl::commit`].
+    pub fn view(&self, payload: &[u8], viewer: &str, now: u64) -> Result<View, StegError> {
+        let (mut policy, sealed) = self.policy(payload)?;
+        policy.check(viewer, now)?;
+
+        if viewer != policy.owner {
+            if let Some(&lowest) = self.seen.lock().unwrap().get(&policy.id) {
+                if policy.remaining_views > lowest {
+                    return Err(Self::outdated(lowest));
+                }
+            }
+            policy.remaining_views -= 1;
+        }
+
+        let secret = crypto::open(sealed, &self.seal_key())?;
+        let payload = self.assemble(&policy, sealed)?;
+        Ok(View { secret, payload, policy })
+    }
+
+    /// Takes a view returned by [`AccessControl::view`] off the count. Call it once everything
+    /// else the view involves has succeeded. Fails if a concurrent view of the same copy got
+    /// there first.
+    pub fn commit(&self, policy: &AccessPolicy, viewer: &str) -> Result<(), StegError> {
+        if viewer == policy.owner {
+            return Ok(());
+        }
+        let mut seen = self.seen.lock().unwrap();
+        let lowest = seen.entry(policy.id).or_insert(policy.remaining_views + 1);
+        if policy.remaining_views >= *lowest {
+            return Err(Self::outdated(*lowest));
+        }
+        *lowest = policy.remaining_views;
+        Ok(())
+    }
+
+    fn outdated(lowest: u32) -> StegError {
+        StegError::AccessDenied(format!("this is an outdated copy of the image, only {} views are left", lowest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(views: u32) -> AccessRules {
+        AccessRules { viewers: vec!["bob".to_string()], views, expires_at: None }
+    }
+
+    #[test]
+    fn views_are_counted_down_until_exhausted() {
+        let access = AccessControl::new(ClusterKey::generate());
+        let policy = AccessPolicy::new("alice", &rules(2));
+        let mut payload = access.protect(&policy, b"holiday.jpg").unwrap();
+        assert_eq!(payload.len(), b"holiday.jpg".len() + access.overhead(&policy));
+        assert!(!payload.windows(11).any(|w| w == b"holiday.jpg"));
+
+        for left in [1, 0] {
+            let view = access.view(&payload, "bob", now()).unwrap();
+            access.commit(&view.policy, "bob").unwrap();
+            assert_eq!(view.secret, b"holiday.jpg");
+            assert_eq!(view.policy.remaining_views, left);
+            payload = view.payload;
+        }
+        assert!(access.view(&payload, "bob", now()).is_err());
+        // The owner is neither counted nor refused
+        let view = access.view(&payload, "alice", now()).unwrap();
+        assert_eq!(view.policy.remaining_views, 0);
+    }
+
+    #[test]
+    fn strangers_expired_policies_and_stale_copies_are_refused() {
+        let access = AccessControl::new(ClusterKey::generate());
+        let policy = AccessPolicy::new("alice", &rules(5));
+        let original = access.protect(&policy, b"secret").unwrap();
+        assert!(matches!(access.view(&original, "mallory", now()), Err(StegError::AccessDenied(_))));
+
+        let view = access.view(&original, "bob", now()).unwrap();
+        access.commit(&view.policy, "bob").unwrap();
+        assert!(access.view(&original, "bob", now()).is_err());
+        assert!(access.commit(&view.policy, "bob").is_err());
+
+        let expiring = AccessPolicy::new("alice", &AccessRules { expires_at: Some(100), ..rules(5) });
+        let payload = access.protect(&expiring, b"secret").unwrap();
+        assert!(access.view(&payload, "bob", 99).is_ok());
+        assert!(access.view(&payload, "bob", 100).is_err());
+    }
+
+    #[test]
+    fn views_only_count_once_committed() {
+        let access = AccessControl::new(ClusterKey::generate());
+        let payload = access.protect(&AccessPolicy::new("alice", &rules(1)), b"secret").unwrap();
+        access.view(&payload, "bob", now()).unwrap();
+        let view = access.view(&payload, "bob", now()).unwrap();
+        assert_eq!(view.policy.remaining_views, 0);
+        access.commit(&view.policy, "bob").unwrap();
+        assert!(access.view(&payload, "bob", now()).is_err());
+    }
+
+    #[test]
+    fn policies_cannot_be_forged() {
+        let access = AccessControl::new(ClusterKey::generate());
+        let policy = AccessPolicy::new("alice", &rules(1));
+        let payload = access.protect(&policy, b"secret").unwrap();
+
+        // Granting more views breaks the signature
+        let mut generous = policy.clone();
+        generous.remaining_views = 1000;
+        let (_, sealed) = access.policy(&payload).unwrap();
+        let mut forged = AccessControl::new(ClusterKey::generate()).assemble(&generous, sealed).unwrap();
+        assert!(access.view(&forged, "bob", now()).is_err());
+        let at = forged.len() - 1;
+        forged[at] ^= 1;
+        assert!(access.policy(&forged).is_err());
+    }
+}
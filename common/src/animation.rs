@@ -0,0 +1,233 @@
+//! LSB steganography in animated GIFs.
+//!
+//! GIF pixels are indices into a palette of at most 256 colours, so flipping the low bit of a
+//! pixel could turn it into any colour at all. Each bit is carried by the parity of a pixel's
+//! index instead: a pixel of the wrong parity switches to the closest colour of its palette
+//! whose index has the other parity. Transparent pixels are skipped and never made.
+//!
+//! The payload is laid out as in [`crate::stego`], its length as a big-endian `u32` first, but
+//! successive bits go to successive frames, so even a short payload is spread over the whole
+//! animation. The stego GIF keeps the palettes, frame positions, delays, disposal and loop
+//! count of the carrier.
+
+use std::io::Cursor;
+
+use gif::{Decoder, Encoder, Frame, Repeat, SetParameter};
+
+const LEN_BYTES: usize = 4;
+// Application extension holding the loop count, followed by a sub-block of 3 bytes
+const LOOP_EXTENSION: &[u8] = b"NETSCAPE2.0\x03\x01";
+
+/// A decoded GIF, with palette indices rather than colours.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    pub width: u16,
+    pub height: u16,
+    pub global_palette: Option<Vec<u8>>,
+    /// Times the animation repeats, 0 for forever; `None` if it plays once.
+    pub repeat: Option<u16>,
+    pub frames: Vec<Frame<'static>>,
+}
+
+// For every index of a palette but the transparent one, the nearest colour with an index of
+// the other parity, if the palette has one that is not transparent
+fn partners(palette: &[u8], transparent: Option<u8>) -> Vec<Option<u8>> {
+    let colours: Vec<&[u8]> = palette.chunks_exact(3).take(256).collect();
+    let distance = |a: &[u8], b: &[u8]| a.iter().zip(b).map(|(&x, &y)| (i32::from(x) - i32::from(y)).pow(2)).sum::<i32>();
+    (0..colours.len())
+        .map(|i| {
+            if Some(i as u8) == transparent {
+                return None;
+            }
+            (0..colours.len())
+                .filter(|&j| j % 2 != i % 2 && Some(j as u8) != transparent)
+                .min_by_key(|&j| distance(colours[i], colours[j]))
+                .map(|j| j as u8)
+        })
+        .collect()
+}
+
+impl Animation {
+    pub fn read(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Decoder::new(Cursor::new(bytes)).read_info().map_err(|e| e.to_string())?;
+        let global_palette = reader.global_palette().map(<[u8]>::to_vec);
+        let (width, height) = (reader.width(), reader.height());
+        let mut frames = Vec::new();
+        while let Some(frame) = reader.read_next_frame().map_err(|e| e.to_string())? {
+            frames.push(frame.clone());
+        }
+        if frames.is_empty() {
+            return Err("GIF has no frames".to_string());
+        }
+        let repeat = bytes
+            .windows(LOOP_EXTENSION.len() + 2)
+            .find(|window| window.starts_with(LOOP_EXTENSION))
+            .map(|window| u16::from_le_bytes([window[LOOP_EXTENSION.len()], window[LOOP_EXTENSION.len() + 1]]));
+        Ok(Self { width, height, global_palette, repeat, frames })
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>, String> {
+        let mut buffer = Vec::new();
+        {
+            let palette = self.global_palette.as_deref().unwrap_or_default();
+            let mut encoder = Encoder::new(&mut buffer, self.width, self.height, palette).map_err(|e| e.to_string())?;
+            match self.repeat {
+                Some(0) => encoder.set(Repeat::Infinite),
+                Some(times) => encoder.set(Repeat::Finite(times)),
+                None => Ok(()),
+            }
+            .map_err(|e| e.to_string())?;
+            for frame in &self.frames {
+                // Frames were read deinterlaced
+                encoder.write_frame(&Frame { interlaced: false, ..frame.clone() }).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// Number of payload bytes the animation can hold.
+    pub fn capacity(&self) -> usize {
+        let tables = self.partner_tables();
+        let usable = self.frames.iter().zip(&tables).map(|(frame, table)| {
+            frame.buffer.iter().filter(|&&index| usable(table, index)).count()
+        });
+        (usable.sum::<usize>() / 8).saturating_sub(LEN_BYTES)
+    }
+
+    fn partner_tables(&self) -> Vec<Vec<Option<u8>>> {
+        let global = self.global_palette.as_deref().unwrap_or_default();
+        self.frames
+            .iter()
+            .map(|frame| partners(frame.palette.as_deref().unwrap_or(global), frame.transparent))
+            .collect()
+    }
+
+    // Positions of the pixels that carry bits, as (frame, pixel), dealt out to the frames in turn
+    fn slots<'a>(&'a self, tables: &'a [Vec<Option<u8>>]) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let longest = self.frames.iter().map(|frame| frame.buffer.len()).max().unwrap_or_default();
+        (0..longest).flat_map(move |pixel| {
+            self.frames.iter().zip(tables).enumerate().filter_map(move |(f, (frame, table))| {
+                frame.buffer.get(pixel).filter(|&&index| usable(table, index)).map(|_| (f, pixel))
+            })
+        })
+    }
+}
+
+// Pixels of the transparent colour have no partner, nor do any if all colours share a parity
+fn usable(table: &[Option<u8>], index: u8) -> bool {
+    table.get(usize::from(index)).is_some_and(Option::is_some)
+}
+
+pub fn is_gif(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a")
+}
+
+/// Hides `payload` in a copy of `carrier`.
+pub fn embed(carrier: &Animation, payload: &[u8]) -> Result<Animation, String> {
+    let available = carrier.capacity();
+    if payload.len() > available {
+        return Err(format!(
+            "payload of {} bytes does not fit into {} frames, which hold {} bytes",
+            payload.len(), carrier.frames.len(), available
+        ));
+    }
+    let tables = carrier.partner_tables();
+    let len = (payload.len() as u32).to_be_bytes();
+    let bits = len.iter().chain(payload).flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1));
+    let slots: Vec<(usize, usize)> = carrier.slots(&tables).take((LEN_BYTES + payload.len()) * 8).collect();
+
+    let mut stego = carrier.clone();
+    for ((f, pixel), bit) in slots.into_iter().zip(bits) {
+        let buffer = stego.frames[f].buffer.to_mut();
+        if buffer[pixel] & 1 != bit {
+            buffer[pixel] = tables[f][usize::from(buffer[pixel])].expect("slots are usable");
+        }
+    }
+    Ok(stego)
+}
+
+/// Reads back a payload written by [`embed`].
+pub fn extract(stego: &Animation) -> Result<Vec<u8>, String> {
+    peek(stego, usize::MAX).map(|(_, payload)| payload)
+}
+
+/// Reads the declared length of a payload written by [`embed`] and at most `limit` of its
+/// leading bytes.
+pub fn peek(stego: &Animation, limit: usize) -> Result<(usize, Vec<u8>), String> {
+    let tables = stego.partner_tables();
+    let mut bits = stego.slots(&tables).map(|(f, pixel)| stego.frames[f].buffer[pixel] & 1);
+    let mut next_byte = || (0..8).try_fold(0u8, |byte, _| bits.next().map(|bit| byte << 1 | bit));
+
+    let mut len = [0u8; LEN_BYTES];
+    for b in len.iter_mut() {
+        *b = next_byte().ok_or("animation is too small to hold a payload")?;
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > stego.capacity() {
+        return Err("animation does not contain a payload".to_string());
+    }
+    let payload = (0..len.min(limit)).map(|_| next_byte().ok_or_else(|| "payload is truncated".to_string()));
+    Ok((len, payload.collect::<Result<_, _>>()?))
+}
+
+/// Hides `payload` in a GIF and returns the stego animation as GIF.
+pub fn hide(carrier: &[u8], payload: &[u8]) -> Result<Vec<u8>, String> {
+    embed(&Animation::read(carrier)?, payload)?.write()
+}
+
+/// Reads the payload out of a stego animation.
+pub fn reveal(stego: &[u8]) -> Result<Vec<u8>, String> {
+    extract(&Animation::read(stego)?)
+}
+
+/// A GIF of `frames` frames of `side` pixels square, for tests elsewhere in the crate.
+#[cfg(test)]
+pub(crate) fn sample(side: u16, frames: usize) -> Vec<u8> {
+    // A grey ramp, with a local palette on the second frame and a transparent colour on the third
+    let ramp: Vec<u8> = (0..16u8).flat_map(|i| [i * 16, i * 16, i * 16]).collect();
+    let frames = (0..frames)
+        .map(|f| Frame {
+            delay: 5 + f as u16,
+            width: side,
+            height: side,
+            palette: (f == 1).then(|| ramp.iter().map(|c| c / 2).collect()),
+            transparent: (f == 2).then_some(0),
+            buffer: std::borrow::Cow::Owned((0..usize::from(side) * usize::from(side)).map(|i| ((i + f) % 16) as u8).collect()),
+            ..Frame::default()
+        })
+        .collect();
+    Animation { width: side, height: side, global_palette: Some(ramp), repeat: Some(0), frames }.write().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_spreads_over_every_frame() {
+        let carrier = sample(16, 3);
+        let payload = b"holiday snaps";
+        let stego = hide(&carrier, payload).unwrap();
+        assert_eq!(reveal(&stego).unwrap(), payload);
+
+        let (before, after) = (Animation::read(&carrier).unwrap(), Animation::read(&stego).unwrap());
+        assert_eq!((after.repeat, after.global_palette.as_ref()), (Some(0), before.global_palette.as_ref()));
+        for (a, b) in before.frames.iter().zip(&after.frames) {
+            assert_eq!((a.delay, a.transparent, &a.palette), (b.delay, b.transparent, &b.palette));
+            assert_ne!(a.buffer, b.buffer);
+            // Pixels only ever move to a neighbouring shade of the ramp, and never to transparent
+            assert!(a.buffer.iter().zip(b.buffer.iter()).all(|(&x, &y)| x == y || (x.abs_diff(y) == 1 && Some(y) != a.transparent)));
+        }
+    }
+
+    #[test]
+    fn capacity_is_checked() {
+        let carrier = Animation::read(&sample(8, 2)).unwrap();
+        assert_eq!(carrier.capacity(), 2 * 64 / 8 - LEN_BYTES);
+        assert!(embed(&carrier, &vec![0; carrier.capacity() + 1]).is_err());
+        let full = embed(&carrier, &vec![0xa5; carrier.capacity()]).unwrap();
+        assert_eq!(extract(&full).unwrap(), vec![0xa5; carrier.capacity()]);
+        assert!(Animation::read(b"GIF89a but not really").is_err());
+        assert!(!is_gif(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,137 @@
+//! LSB steganography in WAV audio.
+//!
+//! The payload is laid out as in [`crate::stego`]: its length as a big-endian `u32`, then its
+//! bytes, most significant bit first, one bit in the least significant bit of every sample of
+//! every channel. Only integer PCM is supported. The stego audio keeps the sample rate,
+//! channels and bit depth of the carrier, and no sample moves by more than one step.
+
+use std::io::Cursor;
+
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+const LEN_BYTES: usize = 4;
+
+/// A decoded WAV file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Wav {
+    pub spec: WavSpec,
+    /// Samples of all channels, interleaved.
+    pub samples: Vec<i32>,
+}
+
+impl Wav {
+    pub fn read(bytes: &[u8]) -> Result<Self, String> {
+        let reader = WavReader::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+        let spec = reader.spec();
+        if spec.sample_format != SampleFormat::Int {
+            return Err("only integer PCM WAV files are supported".to_string());
+        }
+        let samples = reader.into_samples::<i32>().collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+        Ok(Self { spec, samples })
+    }
+
+    pub fn write(&self) -> Result<Vec<u8>, String> {
+        let mut buffer = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut buffer, self.spec).map_err(|e| e.to_string())?;
+        for &sample in &self.samples {
+            writer.write_sample(sample).map_err(|e| e.to_string())?;
+        }
+        writer.finalize().map_err(|e| e.to_string())?;
+        Ok(buffer.into_inner())
+    }
+
+    /// Number of payload bytes the audio can hold.
+    pub fn capacity(&self) -> usize {
+        capacity(self.samples.len())
+    }
+}
+
+/// Number of payload bytes `samples` samples can hold.
+pub fn capacity(samples: usize) -> usize {
+    (samples / 8).saturating_sub(LEN_BYTES)
+}
+
+/// Hides `payload` in a copy of `carrier`.
+pub fn embed(carrier: &Wav, payload: &[u8]) -> Result<Wav, String> {
+    let available = carrier.capacity();
+    if payload.len() > available {
+        return Err(format!(
+            "payload of {} bytes does not fit into {} samples, which hold {} bytes",
+            payload.len(), carrier.samples.len(), available
+        ));
+    }
+    let mut stego = carrier.clone();
+    let len = (payload.len() as u32).to_be_bytes();
+    let bits = len.iter().chain(payload).flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1));
+    for (sample, bit) in stego.samples.iter_mut().zip(bits) {
+        *sample = (*sample & !1) | i32::from(bit);
+    }
+    Ok(stego)
+}
+
+/// Reads back a payload written by [`embed`].
+pub fn extract(stego: &Wav) -> Result<Vec<u8>, String> {
+    let mut bits = stego.samples.iter().map(|s| (s & 1) as u8);
+    let mut next_byte = || (0..8).try_fold(0u8, |byte, _| bits.next().map(|bit| byte << 1 | bit));
+
+    let mut len = [0u8; LEN_BYTES];
+    for b in len.iter_mut() {
+        *b = next_byte().ok_or("audio is too short to hold a payload")?;
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > stego.capacity() {
+        return Err("audio does not contain a payload".to_string());
+    }
+    (0..len).map(|_| next_byte().ok_or_else(|| "payload is truncated".to_string())).collect()
+}
+
+/// Hides `payload` in a WAV file and returns the stego audio as WAV.
+pub fn hide(carrier: &[u8], payload: &[u8]) -> Result<Vec<u8>, String> {
+    embed(&Wav::read(carrier)?, payload)?.write()
+}
+
+/// Reads the payload out of stego audio.
+pub fn reveal(stego: &[u8]) -> Result<Vec<u8>, String> {
+    extract(&Wav::read(stego)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn carrier(bits_per_sample: u16, samples: usize) -> Vec<u8> {
+        let spec = WavSpec { channels: 2, sample_rate: 8000, bits_per_sample, sample_format: SampleFormat::Int };
+        let amplitude = (1i32 << (bits_per_sample - 2)) as f64;
+        let samples = (0..samples).map(|i| ((i as f64 / 10.0).sin() * amplitude) as i32).collect();
+        Wav { spec, samples }.write().unwrap()
+    }
+
+    #[test]
+    fn payload_round_trips_at_every_bit_depth() {
+        for bits in [8, 16, 24] {
+            let carrier = carrier(bits, 4000);
+            let stego = hide(&carrier, b"voice memo").unwrap();
+            assert_eq!(reveal(&stego).unwrap(), b"voice memo");
+            assert_eq!(Wav::read(&stego).unwrap().spec, Wav::read(&carrier).unwrap().spec);
+        }
+    }
+
+    #[test]
+    fn samples_move_by_at_most_one_step() {
+        let carrier = Wav::read(&carrier(16, 4000)).unwrap();
+        let stego = embed(&carrier, &[0xa5; 400]).unwrap();
+        assert!(carrier.samples.iter().zip(&stego.samples).all(|(a, b)| (a - b).abs() <= 1));
+        assert!(embed(&carrier, &vec![0; carrier.capacity() + 1]).is_err());
+    }
+
+    #[test]
+    fn other_files_are_refused() {
+        assert!(Wav::read(b"RIFF but not really").is_err());
+        let spec = WavSpec { channels: 1, sample_rate: 8000, bits_per_sample: 32, sample_format: SampleFormat::Float };
+        let mut float = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut float, spec).unwrap();
+        writer.write_sample(0.5f32).unwrap();
+        writer.finalize().unwrap();
+        assert!(Wav::read(float.get_ref()).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Issues the cluster CA and the per-node and per-user certificates used by the QUIC endpoints.
+//!
+//! ```text
+//! certgen ca --out certs
+//! certgen node --name node2 --san 10.7.19.117 --ca certs --out certs/node2
+//! certgen client --name alice --ca certs --out certs/alice
+//! ```
+//!
+//! A node directory holds `ca.pem`, `node.pem`, `node.key` and the shared `cluster.key` and is
+//! what `P2P_CERT_DIR` should point to on that node. A client directory holds `ca.pem`,
+//! `client.pem`, `client.key` and `cluster.pub`; the name given here is the user name looked
+//! up in the nodes' users file.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use common::certs::{
+    self, ClusterKey, CA_CERT_FILE, CA_KEY_FILE, CLIENT_CERT_FILE, CLIENT_KEY_FILE, CLUSTER_KEY_FILE, CLUSTER_PUB_FILE,
+    NODE_CERT_FILE, NODE_KEY_FILE,
+};
+
+#[derive(Parser, Debug)]
+#[clap(name = "certgen")]
+struct Opt {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Create a new cluster CA and cluster signing key
+    Ca {
+        /// Directory to write ca.pem, ca.key, cluster.key and cluster.pub to
+        #[clap(long = "out", default_value = "certs")]
+        out: PathBuf,
+        /// Common name of the CA certificate
+        #[clap(long = "name", default_value = "Cloud P2P Cluster CA")]
+        name: String,
+    },
+    /// Issue a node certificate signed by the cluster CA
+    Node {
+        /// Common name of the node certificate
+        #[clap(long = "name")]
+        name: String,
+        /// Address or host name the node is reached at; repeat for several
+        #[clap(long = "san", required = true)]
+        san: Vec<String>,
+        /// Directory holding ca.pem and ca.key
+        #[clap(long = "ca", default_value = "certs")]
+        ca: PathBuf,
+        /// Directory to write the node bundle to
+        #[clap(long = "out")]
+        out: PathBuf,
+    },
+    /// Issue a client certificate identifying a user
+    Client {
+        /// User name, as listed in the users file of the nodes
+        #[clap(long = "name")]
+        name: String,
+        /// Directory holding ca.pem and ca.key
+        #[clap(long = "ca", default_value = "certs")]
+        ca: PathBuf,
+        /// Directory to write the client bundle to
+        #[clap(long = "out")]
+        out: PathBuf,
+    },
+}
+
+// CAs created before the cluster key existed have none to hand out. The cluster key is
+// private, so its copy is made owner-only even if the original is not.
+fn copy_if_present(from: &Path, to: &Path, file: &str) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    if from.join(file).exists() {
+        if file == CLUSTER_KEY_FILE {
+            certs::write_private(&to.join(file), &fs::read(from.join(file))?)?;
+        } else {
+            fs::copy(from.join(file), to.join(file))?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    match Opt::parse().command {
+        Command::Ca { out, name } => {
+            if out.join(CA_KEY_FILE).exists() {
+                return Err(format!("{} already exists, refusing to overwrite the CA", out.join(CA_KEY_FILE).display()).into());
+            }
+            certs::generate_ca(&name)?.write(&out, CA_CERT_FILE, CA_KEY_FILE)?;
+            ClusterKey::generate().write(&out)?;
+            println!("CA and cluster key written to {}", out.display());
+        }
+        Command::Node { name, san, ca: ca_dir, out } => {
+            let ca = certs::load_ca(&ca_dir)?;
+            certs::generate_node_cert(&ca, &name, &san)?.write(&out, NODE_CERT_FILE, NODE_KEY_FILE)?;
+            fs::write(out.join(CA_CERT_FILE), &ca.cert_pem)?;
+            copy_if_present(&ca_dir, &out, CLUSTER_KEY_FILE)?;
+            println!("Certificate for {} ({}) written to {}", name, san.join(", "), out.display());
+        }
+        Command::Client { name, ca: ca_dir, out } => {
+            let ca = certs::load_ca(&ca_dir)?;
+            certs::generate_client_cert(&ca, &name)?.write(&out, CLIENT_CERT_FILE, CLIENT_KEY_FILE)?;
+            fs::write(out.join(CA_CERT_FILE), &ca.cert_pem)?;
+            copy_if_present(&ca_dir, &out, CLUSTER_PUB_FILE)?;
+            println!("Client certificate for {} written to {}", name, out.display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! Checks the provenance signature of a stego image, animated GIF or WAV file offline, with
+//! nothing but the cluster's public key. What is hidden in the file is not revealed.
+//!
+//! ```text
+//! stegverify encoded_images/stego_cat.png --key certs/alice/cluster.pub
+//! ```
+//!
+//! Exits with an error unless the file was signed by the cluster and is intact.
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Parser;
+use common::animation;
+use common::audio;
+use common::certs::{self, CLUSTER_PUB_FILE};
+use common::dct::DctParams;
+use common::fec;
+use common::provenance::{self, Integrity};
+use common::stego::{self, EmbedKey};
+
+#[derive(Parser, Debug)]
+#[clap(name = "stegverify")]
+struct Opt {
+    /// Stego image, animated GIF or WAV file to check
+    file: PathBuf,
+    /// Public key of the cluster; defaults to cluster.pub in P2P_CERT_DIR
+    #[clap(long = "key")]
+    key: Option<PathBuf>,
+    /// JPEG quality the nodes embed DCT payloads for
+    #[clap(long = "quality", default_value = "75")]
+    quality: u8,
+    /// Embedding key, for images hidden in scattered mode
+    #[clap(long = "embed-key")]
+    embed_key: Option<String>,
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let opt = Opt::parse();
+    let key_path = opt.key.unwrap_or_else(|| {
+        PathBuf::from(std::env::var("P2P_CERT_DIR").unwrap_or_else(|_| "certs".to_string())).join(CLUSTER_PUB_FILE)
+    });
+    let key = certs::load_cluster_pub(&key_path)?;
+    let bytes = fs::read(&opt.file).map_err(|e| format!("{}: {}", opt.file.display(), e))?;
+
+    // Files without a payload are reported as unsigned
+    let payload = if bytes.starts_with(b"RIFF") {
+        audio::reveal(&bytes).unwrap_or_default()
+    } else if animation::is_gif(&bytes) {
+        animation::reveal(&bytes).unwrap_or_default()
+    } else {
+        let image = image::load_from_memory(&bytes).map_err(|e| format!("{}: {}", opt.file.display(), e))?;
+        let params = DctParams { quality: opt.quality, max_pixel_diff: u8::MAX };
+        let scattered = opt.embed_key.map(|key| stego::extract_scattered(&image, &EmbedKey(key)));
+        match scattered {
+            Some(Ok(payload)) if provenance::is_signed(&payload) || fec::is_protected(&payload) => payload,
+            _ => stego::extract_any(&image, &params).map(|(_, payload)| payload).unwrap_or_default(),
+        }
+    };
+    let payload = fec::recover(payload).map(|recovered| recovered.payload).unwrap_or_default();
+    let report = provenance::verify(&payload, &key);
+
+    println!("File:      {}", opt.file.display());
+    println!("Integrity: {:?}", report.integrity);
+    if let Some(signer) = &report.signer {
+        println!("Signer:    {} (cluster key {})", signer, provenance::fingerprint(&key));
+    }
+    if let Some(signed_at) = report.signed_at {
+        println!("Signed at: {} (Unix time)", signed_at);
+    }
+    if report.integrity != Integrity::Intact {
+        return Err(format!("{} is not an intact image signed by this cluster", opt.file.display()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,269 @@
+//! Several files hidden together, e.g. a folder of photos and a note about them.
+//!
+//! ```text
+//! P2PB | version (1) | part (u16 BE) | manifest length (u32 BE) | manifest (bincode)
+//!      | for every file of the part: container length (u32 BE) | container
+//! ```
+//!
+//! Each file goes into its own [`crate::container`], so it keeps its name, MIME type and
+//! metadata. A bundle too big for one carrier is split into parts, one per carrier. Files are
+//! never split, and every part carries the whole manifest, so any one stego image tells which
+//! files belong to the bundle and which part holds each of them.
+//!
+//! A bundle takes the place of the single container as the innermost layer; encryption,
+//! signatures and error correction wrap each part the same way.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::container::{self, ContainerError, HiddenFile};
+use crate::error::StegError;
+
+const MAGIC: &[u8; 4] = b"P2PB";
+pub const VERSION: u8 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 2 + 4;
+const LEN_BYTES: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub mime: String,
+    pub size: u64,
+    /// Index of the part, and so of the stego image, that holds the file.
+    pub part: u16,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Random id telling the parts of one bundle from those of another.
+    pub id: u64,
+    pub parts: u16,
+    pub entries: Vec<Entry>,
+}
+
+/// What was read from the stego images of a bundle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bundle {
+    /// Every file of the bundle, whether its part was among the images or not.
+    pub entries: Vec<Entry>,
+    /// The files in the parts that were given, in manifest order.
+    pub files: Vec<HiddenFile>,
+}
+
+impl Bundle {
+    /// A bundle of the one file an image hidden with plain `encode` holds.
+    pub fn single(file: HiddenFile) -> Self {
+        let mime = file.mime.clone();
+        let entry = Entry { name: file.name.clone(), mime, size: file.content.len() as u64, part: 0 };
+        Self { entries: vec![entry], files: vec![file] }
+    }
+
+    /// Entries whose part was not among the images.
+    pub fn missing(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|entry| !self.files.iter().any(|file| file.name == entry.name))
+    }
+}
+
+pub fn is_bundle(payload: &[u8]) -> bool {
+    payload.starts_with(MAGIC)
+}
+
+/// Packs `files` into as few parts as it can, where part `n` may be at most `budget(n)` bytes.
+/// File names must be unique within the bundle.
+pub fn pack(files: &[HiddenFile], mut budget: impl FnMut(usize) -> usize) -> Result<Vec<Vec<u8>>, StegError> {
+    if files.is_empty() {
+        return Err(StegError::InvalidRequest("a bundle needs at least one file".to_string()));
+    }
+    let mut names = BTreeSet::new();
+    if let Some(file) = files.iter().find(|file| !names.insert(file.name.as_str())) {
+        return Err(StegError::InvalidRequest(format!("{:?} is in the bundle twice", file.name)));
+    }
+    let containers = files
+        .iter()
+        .map(|file| container::pack(file).map_err(StegError::InvalidRequest))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut manifest = Manifest {
+        id: rand::random(),
+        parts: 0,
+        entries: files
+            .iter()
+            .map(|file| Entry { name: file.name.clone(), mime: file.mime.clone(), size: file.content.len() as u64, part: 0 })
+            .collect(),
+    };
+    // Part numbers are fixed size, so the manifest is as long now as it will be
+    let manifest_len = bincode::serialized_size(&manifest).map_err(|e| StegError::Internal(e.to_string()))? as usize;
+    let empty = HEADER_LEN + manifest_len;
+
+    let mut parts: Vec<Vec<usize>> = vec![Vec::new()];
+    let mut len = empty;
+    for (i, packed) in containers.iter().enumerate() {
+        let needed = LEN_BYTES + packed.len();
+        if len + needed > budget(parts.len() - 1) && !parts.last().unwrap().is_empty() {
+            parts.push(Vec::new());
+            len = empty;
+        }
+        let available = budget(parts.len() - 1);
+        if len + needed > available {
+            return Err(StegError::PayloadTooLarge { payload: (len + needed) as u64, capacity: available as u64 });
+        }
+        parts.last_mut().unwrap().push(i);
+        len += needed;
+    }
+    manifest.parts = u16::try_from(parts.len())
+        .map_err(|_| StegError::InvalidRequest("a bundle cannot be split into that many parts".to_string()))?;
+    for (part, members) in parts.iter().enumerate() {
+        for &i in members {
+            manifest.entries[i].part = part as u16;
+        }
+    }
+
+    let manifest = bincode::serialize(&manifest).map_err(|e| StegError::Internal(e.to_string()))?;
+    Ok(parts
+        .iter()
+        .enumerate()
+        .map(|(part, members)| {
+            let mut payload = MAGIC.to_vec();
+            payload.push(VERSION);
+            payload.extend_from_slice(&(part as u16).to_be_bytes());
+            payload.extend_from_slice(&(manifest.len() as u32).to_be_bytes());
+            payload.extend_from_slice(&manifest);
+            for &i in members {
+                payload.extend_from_slice(&(containers[i].len() as u32).to_be_bytes());
+                payload.extend_from_slice(&containers[i]);
+            }
+            payload
+        })
+        .collect())
+}
+
+/// One part of a bundle as read from a stego image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Part {
+    pub index: u16,
+    pub manifest: Manifest,
+    pub files: Vec<HiddenFile>,
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize, what: &str) -> Result<&'a [u8], ContainerError> {
+    if bytes.len() < len {
+        return Err(ContainerError::Corrupt(format!("{} is truncated", what)));
+    }
+    let (taken, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(taken)
+}
+
+fn u32_at(bytes: &mut &[u8], what: &str) -> Result<usize, ContainerError> {
+    Ok(u32::from_be_bytes(take(bytes, LEN_BYTES, what)?.try_into().unwrap()) as usize)
+}
+
+pub fn unpack(payload: &[u8]) -> Result<Part, ContainerError> {
+    if !is_bundle(payload) {
+        return Err(ContainerError::NoPayload);
+    }
+    let mut bytes = &payload[MAGIC.len()..];
+    let version = take(&mut bytes, 1, "bundle version")?[0];
+    if version != VERSION {
+        return Err(ContainerError::UnsupportedVersion(version));
+    }
+    let index = u16::from_be_bytes(take(&mut bytes, 2, "part number")?.try_into().unwrap());
+    let manifest_len = u32_at(&mut bytes, "manifest")?;
+    let manifest: Manifest = bincode::deserialize(take(&mut bytes, manifest_len, "manifest")?)
+        .map_err(|e| ContainerError::Corrupt(format!("manifest is malformed: {}", e)))?;
+    if index >= manifest.parts {
+        return Err(ContainerError::Corrupt(format!("part {} of a bundle of {}", index, manifest.parts)));
+    }
+
+    let mut files = Vec::new();
+    while !bytes.is_empty() {
+        let len = u32_at(&mut bytes, "file")?;
+        files.push(container::unpack(take(&mut bytes, len, "file")?)?);
+    }
+    let expected: Vec<&str> = manifest.entries.iter().filter(|e| e.part == index).map(|e| e.name.as_str()).collect();
+    if files.iter().map(|file| file.name.as_str()).ne(expected) {
+        return Err(ContainerError::Corrupt(format!("part {} does not hold the files its manifest lists", index)));
+    }
+    Ok(Part { index, manifest, files })
+}
+
+/// Puts the parts read from the stego images of one bundle back together. Parts may come in
+/// any order and some may be missing; [`Bundle::missing`] tells which files are.
+pub fn join(parts: Vec<Part>) -> Result<Bundle, StegError> {
+    let manifest = match parts.first() {
+        Some(part) => part.manifest.clone(),
+        None => return Err(StegError::InvalidRequest("no images were given".to_string())),
+    };
+    if parts.iter().any(|part| part.manifest != manifest) {
+        return Err(StegError::InvalidRequest("the images belong to different bundles".to_string()));
+    }
+    let mut files: Vec<HiddenFile> = Vec::new();
+    for part in parts {
+        for file in part.files {
+            if !files.iter().any(|known| known.name == file.name) {
+                files.push(file);
+            }
+        }
+    }
+    let order = |file: &HiddenFile| manifest.entries.iter().position(|entry| entry.name == file.name);
+    files.sort_by_key(order);
+    Ok(Bundle { entries: manifest.entries, files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, size: usize) -> HiddenFile {
+        HiddenFile {
+            name: name.to_string(),
+            mime: "image/jpeg".to_string(),
+            content: vec![name.len() as u8; size],
+            ..Default::default()
+        }
+    }
+
+    fn folder() -> Vec<HiddenFile> {
+        vec![file("beach.jpg", 300), file("dunes.jpg", 300), file("note.txt", 40)]
+    }
+
+    #[test]
+    fn folders_round_trip_in_one_part() {
+        let parts = pack(&folder(), |_| 10_000).unwrap();
+        assert_eq!(parts.len(), 1);
+        let bundle = join(vec![unpack(&parts[0]).unwrap()]).unwrap();
+        assert_eq!(bundle.files, folder());
+        assert_eq!(bundle.entries.iter().map(|e| (e.size, e.part)).collect::<Vec<_>>(), vec![(300, 0), (300, 0), (40, 0)]);
+        assert_eq!(bundle.missing().count(), 0);
+    }
+
+    #[test]
+    fn large_folders_are_split_by_file() {
+        let parts = pack(&folder(), |_| 900).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|part| part.len() <= 900));
+
+        let second = unpack(&parts[1]).unwrap();
+        assert_eq!(second.manifest.parts, 2);
+        let partial = join(vec![second.clone()]).unwrap();
+        assert_eq!(partial.missing().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["beach.jpg"]);
+        let whole = join(vec![second, unpack(&parts[0]).unwrap()]).unwrap();
+        assert_eq!(whole.files, folder());
+
+        let other = unpack(&pack(&folder(), |_| 900).unwrap()[0]).unwrap();
+        assert!(matches!(join(vec![other, unpack(&parts[1]).unwrap()]), Err(StegError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn bad_bundles_are_refused() {
+        assert!(matches!(pack(&folder(), |_| 200), Err(StegError::PayloadTooLarge { .. })));
+        assert!(matches!(pack(&[file("a", 1), file("a", 2)], |_| 10_000), Err(StegError::InvalidRequest(_))));
+        assert!(matches!(pack(&[file("../a", 1)], |_| 10_000), Err(StegError::InvalidRequest(_))));
+        assert!(matches!(pack(&[], |_| 10_000), Err(StegError::InvalidRequest(_))));
+
+        let part = pack(&folder(), |_| 10_000).unwrap().remove(0);
+        assert!(matches!(unpack(&part[..part.len() - 1]), Err(ContainerError::Corrupt(_))));
+        assert_eq!(unpack(b"P2PC"), Err(ContainerError::NoPayload));
+    }
+}
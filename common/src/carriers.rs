@@ -0,0 +1,340 @@
+//! Carrier images: the ones a client uploads with its secret and the library a node keeps.
+//!
+//! The library is a directory with a `carriers.toml` index naming each image and its tags:
+//!
+//! ```toml
+//! [carriers.beach]
+//! file = "beach.png"
+//! tags = ["landscape", "large"]
+//! ```
+//!
+//! Images are decoded once when the library is loaded. Every selection checks the payload
+//! against the carrier's capacity before anything is embedded.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+
+use crate::animation::{self, Animation};
+use crate::error::StegError;
+use crate::stego::Mode;
+
+pub const INDEX_FILE: &str = "carriers.toml";
+/// Carrier used when a node has no library, read from its working directory.
+pub const LEGACY_CARRIER: &str = "carrier.png";
+
+/// Which carrier to hide a secret in.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Carrier {
+    /// Let the server pick the smallest library carrier the secret fits in.
+    Default,
+    /// An image supplied by the client, in any format the server can read. Animated GIFs
+    /// keep their frames and hold the payload across all of them.
+    Image(Vec<u8>),
+    /// A library carrier by id.
+    Library(String),
+    /// The smallest library carrier with this tag that the secret fits in.
+    Tagged(String),
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    file: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Index {
+    #[serde(default)]
+    carriers: BTreeMap<String, IndexEntry>,
+}
+
+pub struct LibraryCarrier {
+    pub id: String,
+    pub tags: Vec<String>,
+    pub image: Arc<DynamicImage>,
+}
+
+type Candidate = (Option<String>, Arc<DynamicImage>);
+
+#[derive(Default)]
+pub struct CarrierLibrary {
+    carriers: Vec<LibraryCarrier>,
+}
+
+/// Capacity of one carrier in every mode.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarrierCapacity {
+    /// Library id, `None` for an image supplied by the client.
+    pub id: Option<String>,
+    pub width: u32,
+    pub height: u32,
+    pub modes: Vec<(Mode, usize)>,
+}
+
+impl CarrierCapacity {
+    fn of(id: Option<String>, image: &DynamicImage) -> Self {
+        let (width, height) = image.dimensions();
+        Self {
+            id,
+            width,
+            height,
+            modes: Mode::ALL.iter().map(|&mode| (mode, mode.capacity(width, height))).collect(),
+        }
+    }
+
+    // Animated GIFs only hold payloads in LSB mode
+    fn of_animation(animation: &Animation) -> Self {
+        Self {
+            id: None,
+            width: u32::from(animation.width),
+            height: u32::from(animation.height),
+            modes: vec![(Mode::Lsb, animation.capacity())],
+        }
+    }
+
+    /// Payload bytes the carrier holds in `mode`.
+    pub fn bytes(&self, mode: Mode) -> usize {
+        self.modes.iter().find(|(m, _)| *m == mode).map_or(0, |(_, bytes)| *bytes)
+    }
+}
+
+/// Outcome of checking a secret size against a carrier without encoding anything.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FitReport {
+    pub fits: bool,
+    pub payload: usize,
+    pub mode: Mode,
+    /// The carrier `encode` would use; the largest candidate if none is big enough.
+    pub carrier: CarrierCapacity,
+}
+
+impl FitReport {
+    pub fn capacity(&self) -> usize {
+        self.carrier.bytes(self.mode)
+    }
+
+    /// Bytes left over in the carrier after the secret, zero if it does not fit.
+    pub fn headroom(&self) -> usize {
+        self.capacity().saturating_sub(self.payload)
+    }
+}
+
+impl CarrierLibrary {
+    pub fn new(carriers: Vec<LibraryCarrier>) -> Self {
+        Self { carriers }
+    }
+
+    /// Loads the library in `dir` as described by its index file.
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let index_path = dir.join(INDEX_FILE);
+        let text = fs::read_to_string(&index_path).map_err(|e| format!("{}: {}", index_path.display(), e))?;
+        let index: Index = toml::from_str(&text).map_err(|e| format!("{}: {}", index_path.display(), e))?;
+
+        let mut carriers = Vec::new();
+        for (id, entry) in index.carriers {
+            let path = dir.join(&entry.file);
+            let image = image::open(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            carriers.push(LibraryCarrier { id, tags: entry.tags, image: Arc::new(image) });
+        }
+        Ok(Self::new(carriers))
+    }
+
+    /// Loads the library in `P2P_CARRIER_DIR` (default `carriers`). Without one, the node
+    /// falls back to a single `default` carrier read from `carrier.png`, if that exists.
+    pub fn from_env() -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let dir = env::var("P2P_CARRIER_DIR").unwrap_or_else(|_| "carriers".to_string());
+        let dir = Path::new(&dir);
+        if dir.join(INDEX_FILE).exists() {
+            return Self::load(dir);
+        }
+        if Path::new(LEGACY_CARRIER).exists() {
+            let image = image::open(LEGACY_CARRIER).map_err(|e| format!("{}: {}", LEGACY_CARRIER, e))?;
+            return Ok(Self::new(vec![LibraryCarrier {
+                id: "default".to_string(),
+                tags: Vec::new(),
+                image: Arc::new(image),
+            }]));
+        }
+        Ok(Self::default())
+    }
+
+    pub fn carriers(&self) -> &[LibraryCarrier] {
+        &self.carriers
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LibraryCarrier> {
+        self.carriers.iter().find(|c| c.id == id)
+    }
+
+    /// The images `carrier` may resolve to, with their library ids.
+    fn candidates(&self, carrier: &Carrier) -> Result<Vec<Candidate>, StegError> {
+        let library = |entries: Vec<&LibraryCarrier>| {
+            entries.into_iter().map(|c| (Some(c.id.clone()), c.image.clone())).collect::<Vec<_>>()
+        };
+        let candidates = match carrier {
+            Carrier::Default => library(self.carriers.iter().collect()),
+            Carrier::Image(bytes) => {
+                let image = image::load_from_memory(bytes).map_err(|e| StegError::InvalidImage(format!("carrier: {}", e)))?;
+                vec![(None, Arc::new(image))]
+            }
+            Carrier::Library(id) => {
+                let entry = self.get(id).ok_or_else(|| StegError::CarrierUnavailable(format!("no carrier with id {:?}", id)))?;
+                library(vec![entry])
+            }
+            Carrier::Tagged(tag) => library(self.carriers.iter().filter(|c| c.tags.contains(tag)).collect()),
+        };
+        if candidates.is_empty() {
+            return Err(StegError::CarrierUnavailable(format!("there is no {}", describe(carrier))));
+        }
+        Ok(candidates)
+    }
+
+    // The candidates with their capacities; an animated GIF holds as much as all its frames
+    fn sized(&self, carrier: &Carrier) -> Result<Vec<(CarrierCapacity, Arc<DynamicImage>)>, StegError> {
+        let candidates = self.candidates(carrier)?;
+        if let Carrier::Image(bytes) = carrier {
+            if animation::is_gif(bytes) {
+                let animation = Animation::read(bytes).map_err(|e| StegError::InvalidImage(format!("carrier: {}", e)))?;
+                let capacity = CarrierCapacity::of_animation(&animation);
+                return Ok(candidates.into_iter().map(|(_, image)| (capacity.clone(), image)).collect());
+            }
+        }
+        Ok(candidates.into_iter().map(|(id, image)| (CarrierCapacity::of(id, &image), image)).collect())
+    }
+
+    /// Capacity of every image `carrier` may resolve to.
+    pub fn capacities(&self, carrier: &Carrier) -> Result<Vec<CarrierCapacity>, StegError> {
+        Ok(self.sized(carrier)?.into_iter().map(|(capacity, _)| capacity).collect())
+    }
+
+    fn choose(&self, carrier: &Carrier, payload: usize, mode: Mode) -> Result<(FitReport, Arc<DynamicImage>), StegError> {
+        let sized = self.sized(carrier)?;
+        let fitting = sized.iter().filter(|(c, _)| c.bytes(mode) >= payload).min_by_key(|(c, _)| c.bytes(mode));
+        let (chosen, image) = match fitting {
+            Some(chosen) => chosen,
+            None => sized.iter().max_by_key(|(c, _)| c.bytes(mode)).expect("candidates are never empty"),
+        };
+        let report = FitReport { fits: fitting.is_some(), payload, mode, carrier: chosen.clone() };
+        Ok((report, image.clone()))
+    }
+
+    /// Checks whether `payload` bytes fit into `carrier` in `mode`, picking the carrier the way
+    /// [`CarrierLibrary::select`] would.
+    pub fn check_fit(&self, carrier: &Carrier, payload: usize, mode: Mode) -> Result<FitReport, StegError> {
+        self.choose(carrier, payload, mode).map(|(report, _)| report)
+    }
+
+    /// Resolves `carrier` to an image that can hold `payload` bytes in `mode`. Library
+    /// selections by tag or default take the smallest carrier that is big enough. An animated
+    /// GIF resolves to its first frame; [`crate::animation`] embeds in all of them.
+    pub fn select(&self, carrier: &Carrier, payload: usize, mode: Mode) -> Result<Arc<DynamicImage>, StegError> {
+        let (report, image) = self.choose(carrier, payload, mode)?;
+        if !report.fits {
+            return Err(StegError::PayloadTooLarge { payload: payload as u64, capacity: report.capacity() as u64 });
+        }
+        Ok(image)
+    }
+}
+
+fn describe(carrier: &Carrier) -> String {
+    match carrier {
+        Carrier::Default => "carrier in the library".to_string(),
+        Carrier::Image(_) => "the supplied carrier".to_string(),
+        Carrier::Library(id) => format!("carrier {:?}", id),
+        Carrier::Tagged(tag) => format!("carrier tagged {:?}", tag),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stego;
+    use image::{Rgba, RgbaImage};
+
+    fn carrier(id: &str, side: u32, tags: &[&str]) -> LibraryCarrier {
+        LibraryCarrier {
+            id: id.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            image: Arc::new(DynamicImage::ImageRgba8(RgbaImage::from_pixel(side, side, Rgba([9, 9, 9, 255])))),
+        }
+    }
+
+    fn library() -> CarrierLibrary {
+        CarrierLibrary::new(vec![
+            carrier("small", 16, &["icon"]),
+            carrier("medium", 64, &["photo"]),
+            carrier("large", 128, &["photo"]),
+        ])
+    }
+
+    #[test]
+    fn picks_the_smallest_carrier_that_fits() {
+        let library = library();
+        let fits_medium = stego::capacity(64, 64);
+        assert_eq!(library.select(&Carrier::Default, 10, Mode::Lsb).unwrap().width(), 16);
+        assert_eq!(library.select(&Carrier::Default, fits_medium, Mode::Lsb).unwrap().width(), 64);
+        assert_eq!(library.select(&Carrier::Tagged("photo".into()), 10, Mode::Lsb).unwrap().width(), 64);
+        assert_eq!(library.select(&Carrier::Tagged("photo".into()), fits_medium + 1, Mode::Lsb).unwrap().width(), 128);
+        assert!(library.select(&Carrier::Tagged("icon".into()), fits_medium, Mode::Lsb).is_err());
+        assert!(library.select(&Carrier::Tagged("nothing".into()), 1, Mode::Lsb).is_err());
+    }
+
+    #[test]
+    fn capacity_is_checked_before_embedding() {
+        let library = library();
+        assert!(library.select(&Carrier::Library("small".into()), stego::capacity(16, 16), Mode::Lsb).is_ok());
+        let err = library.select(&Carrier::Library("small".into()), stego::capacity(16, 16) + 1, Mode::Lsb).err().unwrap();
+        assert_eq!(err, StegError::PayloadTooLarge {
+            payload: stego::capacity(16, 16) as u64 + 1,
+            capacity: stego::capacity(16, 16) as u64,
+        });
+        assert!(matches!(
+            library.select(&Carrier::Library("missing".into()), 1, Mode::Lsb),
+            Err(StegError::CarrierUnavailable(_))
+        ));
+        assert!(library.select(&Carrier::Default, stego::capacity(128, 128) + 1, Mode::Lsb).is_err());
+    }
+
+    #[test]
+    fn dry_run_reports_the_carrier_encode_would_use() {
+        let library = library();
+        let report = library.check_fit(&Carrier::Tagged("photo".into()), 100, Mode::Lsb).unwrap();
+        assert!(report.fits);
+        assert_eq!(report.carrier.id.as_deref(), Some("medium"));
+        assert_eq!(report.headroom(), stego::capacity(64, 64) - 100);
+
+        let too_big = stego::capacity(128, 128) + 1;
+        let report = library.check_fit(&Carrier::Default, too_big, Mode::Lsb).unwrap();
+        assert!(!report.fits);
+        assert_eq!(report.carrier.id.as_deref(), Some("large"));
+        assert_eq!(report.headroom(), 0);
+
+        let capacities = library.capacities(&Carrier::Tagged("photo".into())).unwrap();
+        assert_eq!(capacities.len(), 2);
+        assert!(capacities.iter().all(|c| c.modes.len() == Mode::ALL.len()));
+        assert_eq!(capacities[0].bytes(Mode::Lsb), stego::capacity(64, 64));
+    }
+
+    #[test]
+    fn client_carriers_are_decoded_and_checked() {
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(20, 20, Rgba([1, 2, 3, 255])))
+            .write_to(&mut png, image::ImageFormat::PNG)
+            .unwrap();
+        let library = CarrierLibrary::default();
+        assert_eq!(library.select(&Carrier::Image(png.clone()), 10, Mode::Lsb).unwrap().width(), 20);
+        assert!(library.select(&Carrier::Image(png), stego::capacity(20, 20) + 1, Mode::Lsb).is_err());
+        assert!(matches!(
+            library.select(&Carrier::Image(b"not an image".to_vec()), 1, Mode::Lsb),
+            Err(StegError::InvalidImage(_))
+        ));
+    }
+}
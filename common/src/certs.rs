@@ -0,0 +1,336 @@
+//! Cluster certificate authority: issuing the CA, node and client certificates and loading them
+//! back from PEM files for the QUIC endpoints. Also the cluster signing key the nodes use to
+//! vouch for data they embed.
+
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use rcgen::{
+    BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair,
+    KeyUsagePurpose,
+};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::RootCertStore;
+use sha2::{Digest, Sha256};
+
+pub const CA_CERT_FILE: &str = "ca.pem";
+pub const CA_KEY_FILE: &str = "ca.key";
+pub const NODE_CERT_FILE: &str = "node.pem";
+pub const NODE_KEY_FILE: &str = "node.key";
+pub const CLIENT_CERT_FILE: &str = "client.pem";
+pub const CLIENT_KEY_FILE: &str = "client.key";
+pub const CLUSTER_KEY_FILE: &str = "cluster.key";
+pub const CLUSTER_PUB_FILE: &str = "cluster.pub";
+
+/// A PEM encoded certificate together with its private key.
+pub struct IssuedCert {
+    pub cert_pem: String,
+    pub key_pem: String,
+}
+
+impl IssuedCert {
+    pub fn write(&self, dir: &Path, cert_file: &str, key_file: &str) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(cert_file), &self.cert_pem)?;
+        write_private(&dir.join(key_file), self.key_pem.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Writes a private key so that only its owner can read it, whatever the umask. An existing
+/// file is truncated and has its permissions tightened too.
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        let file = options.open(path)?;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        (&file).write_all(contents)
+    }
+    #[cfg(not(unix))]
+    options.open(path)?.write_all(contents)
+}
+
+/// Creates a new self-signed cluster CA.
+pub fn generate_ca(common_name: &str) -> Result<IssuedCert, Box<dyn Error + Send + Sync + 'static>> {
+    let mut params = CertificateParams::new(Vec::<String>::new())?;
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.distinguished_name.push(DnType::CommonName, common_name);
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+
+    let key = KeyPair::generate()?;
+    let cert = params.self_signed(&key)?;
+    Ok(IssuedCert { cert_pem: cert.pem(), key_pem: key.serialize_pem() })
+}
+
+/// Issues a node certificate signed by the cluster CA.
+///
+/// `names` become the subject alternative names; IP addresses are accepted as well as DNS
+/// names, and clients verify the server against the one they dialed.
+pub fn generate_node_cert(
+    ca: &IssuedCert,
+    common_name: &str,
+    names: &[String],
+) -> Result<IssuedCert, Box<dyn Error + Send + Sync + 'static>> {
+    let ca_key = KeyPair::from_pem(&ca.key_pem)?;
+    let ca_cert = CertificateParams::from_ca_cert_pem(&ca.cert_pem)?.self_signed(&ca_key)?;
+
+    let mut params = CertificateParams::new(names.to_vec())?;
+    params.distinguished_name.push(DnType::CommonName, common_name);
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![
+        ExtendedKeyUsagePurpose::ServerAuth,
+        ExtendedKeyUsagePurpose::ClientAuth,
+    ];
+
+    let key = KeyPair::generate()?;
+    let cert = params.signed_by(&key, &ca_cert, &ca_key)?;
+    Ok(IssuedCert { cert_pem: cert.pem(), key_pem: key.serialize_pem() })
+}
+
+/// Issues a client certificate signed by the cluster CA. `user` becomes the common name, which
+/// is what the service provider looks up in its users file.
+pub fn generate_client_cert(ca: &IssuedCert, user: &str) -> Result<IssuedCert, Box<dyn Error + Send + Sync + 'static>> {
+    let ca_key = KeyPair::from_pem(&ca.key_pem)?;
+    let ca_cert = CertificateParams::from_ca_cert_pem(&ca.cert_pem)?.self_signed(&ca_key)?;
+
+    let mut params = CertificateParams::new(Vec::<String>::new())?;
+    params.distinguished_name.push(DnType::CommonName, user);
+    params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+
+    let key = KeyPair::generate()?;
+    let cert = params.signed_by(&key, &ca_cert, &ca_key)?;
+    Ok(IssuedCert { cert_pem: cert.pem(), key_pem: key.serialize_pem() })
+}
+
+/// Loads the self-signed certificate a node presents in TOFU mode, creating it on first use.
+/// It has to survive restarts, otherwise every client would see a changed certificate.
+pub fn load_or_create_self_signed(
+    cert_path: &Path,
+    key_path: &Path,
+) -> Result<(CertificateDer<'static>, PrivateKeyDer<'static>), Box<dyn Error + Send + Sync + 'static>> {
+    if !cert_path.exists() {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()])?;
+        for path in [cert_path, key_path] {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+        }
+        fs::write(cert_path, cert.cert.pem())?;
+        write_private(key_path, cert.key_pair.serialize_pem().as_bytes())?;
+    }
+    let cert = load_certs(cert_path)?.remove(0);
+    Ok((cert, load_private_key(key_path)?))
+}
+
+/// Common name of the subject of `cert`.
+pub fn common_name(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, cert) = x509_parser::parse_x509_certificate(cert.as_ref()).ok()?;
+    let name = cert.subject().iter_common_name().next()?.as_str().ok()?;
+    Some(name.to_string())
+}
+
+/// Whether `cert` was issued to a node rather than a user. Only node certificates may act as
+/// servers, so they alone carry the server authentication usage.
+pub fn is_node_cert(cert: &CertificateDer<'_>) -> bool {
+    let Ok((_, cert)) = x509_parser::parse_x509_certificate(cert.as_ref()) else {
+        return false;
+    };
+    matches!(cert.extended_key_usage(), Ok(Some(usage)) if usage.value.server_auth)
+}
+
+/// Ed25519 key shared by all nodes of a cluster. Nodes sign with it; anyone holding the
+/// public half can check a signature offline. Both halves are stored hex encoded.
+pub struct ClusterKey {
+    signing: SigningKey,
+}
+
+impl ClusterKey {
+    pub fn generate() -> Self {
+        let mut seed = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut seed);
+        Self { signing: SigningKey::from_bytes(&seed) }
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+        fs::create_dir_all(dir)?;
+        write_private(&dir.join(CLUSTER_KEY_FILE), hex::encode(self.signing.to_bytes()).as_bytes())?;
+        fs::write(dir.join(CLUSTER_PUB_FILE), hex::encode(self.verifying_key().to_bytes()))?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let seed: [u8; 32] = hex::decode(text.trim())?
+            .try_into()
+            .map_err(|_| format!("{} does not hold a 32 byte key", path.display()))?;
+        Ok(Self { signing: SigningKey::from_bytes(&seed) })
+    }
+
+    /// Loads the key at `path`, generating it and its public half next to it first if it does
+    /// not exist. Only for deployments without a CA; a cluster shares the key `certgen ca` wrote.
+    pub fn load_or_create(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        if !path.exists() {
+            Self::generate().write(path.parent().unwrap_or(Path::new("")))?;
+        }
+        Self::load(path)
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.signing.verifying_key()
+    }
+
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.signing.sign(message).to_bytes()
+    }
+
+    /// A symmetric key for `purpose`, known to every node of the cluster and nobody else.
+    pub fn derive(&self, purpose: &str) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(purpose.as_bytes());
+        hasher.update(self.signing.to_bytes());
+        hasher.finalize().into()
+    }
+}
+
+pub fn load_cluster_pub(path: &Path) -> Result<VerifyingKey, Box<dyn Error + Send + Sync + 'static>> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let bytes: [u8; 32] = hex::decode(text.trim())?
+        .try_into()
+        .map_err(|_| format!("{} does not hold a 32 byte key", path.display()))?;
+    Ok(VerifyingKey::from_bytes(&bytes)?)
+}
+
+/// Checks a signature made with [`ClusterKey::sign`].
+pub fn verify_signature(key: &VerifyingKey, message: &[u8], signature: &[u8; 64]) -> bool {
+    key.verify(message, &Signature::from_bytes(signature)).is_ok()
+}
+
+pub fn load_ca(dir: &Path) -> Result<IssuedCert, Box<dyn Error + Send + Sync + 'static>> {
+    Ok(IssuedCert {
+        cert_pem: fs::read_to_string(dir.join(CA_CERT_FILE))?,
+        key_pem: fs::read_to_string(dir.join(CA_KEY_FILE))?,
+    })
+}
+
+pub fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>, Box<dyn Error + Send + Sync + 'static>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?);
+    let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(format!("no certificate found in {}", path.display()).into());
+    }
+    Ok(certs)
+}
+
+pub fn load_private_key(path: &Path) -> Result<PrivateKeyDer<'static>, Box<dyn Error + Send + Sync + 'static>> {
+    let mut reader = BufReader::new(File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| format!("no private key found in {}", path.display()).into())
+}
+
+pub fn load_root_store(path: &Path) -> Result<RootCertStore, Box<dyn Error + Send + Sync + 'static>> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert)?;
+    }
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::client::danger::ServerCertVerifier;
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::pki_types::{ServerName, UnixTime};
+    use std::sync::Arc;
+
+    fn verify(ca: &IssuedCert, node: &IssuedCert, name: &str) -> Result<(), rustls::Error> {
+        let mut roots = RootCertStore::empty();
+        let ca_der = rustls_pemfile::certs(&mut ca.cert_pem.as_bytes()).next().unwrap().unwrap();
+        roots.add(ca_der).unwrap();
+        let node_der = rustls_pemfile::certs(&mut node.cert_pem.as_bytes()).next().unwrap().unwrap();
+
+        let verifier = WebPkiServerVerifier::builder_with_provider(
+            Arc::new(roots),
+            Arc::new(rustls::crypto::ring::default_provider()),
+        )
+        .build()
+        .unwrap();
+        verifier
+            .verify_server_cert(&node_der, &[], &ServerName::try_from(name.to_owned()).unwrap(), &[], UnixTime::now())
+            .map(|_| ())
+    }
+
+    #[test]
+    fn node_cert_verifies_against_its_ca() {
+        let ca = generate_ca("test ca").unwrap();
+        let node = generate_node_cert(&ca, "node1", &["10.0.0.1".to_owned(), "node1.local".to_owned()]).unwrap();
+        verify(&ca, &node, "10.0.0.1").unwrap();
+        verify(&ca, &node, "node1.local").unwrap();
+    }
+
+    #[test]
+    fn node_cert_is_rejected_for_other_names_and_cas() {
+        let ca = generate_ca("test ca").unwrap();
+        let other_ca = generate_ca("other ca").unwrap();
+        let node = generate_node_cert(&ca, "node1", &["10.0.0.1".to_owned()]).unwrap();
+        assert!(verify(&ca, &node, "10.0.0.2").is_err());
+        assert!(verify(&other_ca, &node, "10.0.0.1").is_err());
+    }
+
+    #[test]
+    fn client_cert_carries_the_user_name() {
+        let ca = generate_ca("test ca").unwrap();
+        let client = generate_client_cert(&ca, "alice").unwrap();
+        let der = rustls_pemfile::certs(&mut client.cert_pem.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(common_name(&der).as_deref(), Some("alice"));
+        assert!(!is_node_cert(&der));
+        let node = generate_node_cert(&ca, "node1", &["10.0.0.1".to_owned()]).unwrap();
+        assert!(is_node_cert(&rustls_pemfile::certs(&mut node.cert_pem.as_bytes()).next().unwrap().unwrap()));
+    }
+
+    #[test]
+    fn cluster_key_survives_a_round_trip_through_files() {
+        let dir = std::env::temp_dir().join(format!("p2p-cluster-key-{}", rand::random::<u64>()));
+        let key = ClusterKey::load_or_create(&dir.join(CLUSTER_KEY_FILE)).unwrap();
+        let loaded = ClusterKey::load(&dir.join(CLUSTER_KEY_FILE)).unwrap();
+        let public = load_cluster_pub(&dir.join(CLUSTER_PUB_FILE)).unwrap();
+
+        let signature = key.sign(b"message");
+        assert!(verify_signature(&public, b"message", &signature));
+        assert!(!verify_signature(&public, b"other message", &signature));
+        assert_eq!(loaded.derive("purpose"), key.derive("purpose"));
+        assert_ne!(key.derive("purpose"), ClusterKey::generate().derive("purpose"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn private_keys_are_only_readable_by_their_owner() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("p2p-key-modes-{}", rand::random::<u64>()));
+        let ca = generate_ca("test ca").unwrap();
+        ca.write(&dir, CA_CERT_FILE, CA_KEY_FILE).unwrap();
+        ClusterKey::generate().write(&dir).unwrap();
+        // An existing key left readable by an older build is tightened when rewritten
+        fs::set_permissions(dir.join(CA_KEY_FILE), fs::Permissions::from_mode(0o644)).unwrap();
+        ca.write(&dir, CA_CERT_FILE, CA_KEY_FILE).unwrap();
+
+        let mode = |file: &str| fs::metadata(dir.join(file)).unwrap().permissions().mode() & 0o777;
+        assert_eq!((mode(CA_KEY_FILE), mode(CLUSTER_KEY_FILE)), (0o600, 0o600));
+        assert_eq!(load_ca(&dir).unwrap().key_pem, ca.key_pem);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,278 @@
+//! Versioned container around a hidden file, so it comes back byte for byte under its name.
+//!
+//! ```text
+//! P2PC | version (1) | name (u16 BE length + UTF-8) | MIME type (u16 BE length + UTF-8)
+//!      | metadata entries (u16 BE count, each key and value as u16 BE length + UTF-8)
+//!      | content length (u64 BE) | SHA-256 of the content (32) | content
+//! ```
+//!
+//! The container is the innermost layer: encryption and access policies wrap it, so they also
+//! hide the name. Readers refuse versions they do not know instead of guessing.
+//!
+//! Names are plain file names, never paths. Whoever decodes an image may write the file out
+//! under its name, so both sides refuse names that could leave the directory it is saved in.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const MAGIC: &[u8; 4] = b"P2PC";
+pub const VERSION: u8 = 1;
+/// Longest file name, in bytes; longer names are rejected.
+pub const MAX_NAME: usize = 255;
+pub const MAX_MIME: usize = 127;
+const HASH_LEN: usize = 32;
+
+/// A file as it was handed to `encode`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HiddenFile {
+    pub name: String,
+    pub mime: String,
+    pub metadata: BTreeMap<String, String>,
+    pub content: Vec<u8>,
+}
+
+impl HiddenFile {
+    /// Writes the content to `dir`, created if needed, under the file's name. The name is
+    /// checked again, as the image need not have come from a node.
+    pub fn save_in(&self, dir: &Path) -> io::Result<PathBuf> {
+        check_name(&self.name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        fs::create_dir_all(dir)?;
+        let path = dir.join(&self.name);
+        fs::write(&path, &self.content)?;
+        Ok(path)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContainerError {
+    /// The bytes are not a container at all.
+    NoPayload,
+    /// Written by a newer release.
+    UnsupportedVersion(u8),
+    /// A container that was damaged or cut short.
+    Corrupt(String),
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContainerError::NoPayload => write!(f, "image does not contain a payload"),
+            ContainerError::UnsupportedVersion(v) => write!(f, "payload container version {} is not supported", v),
+            ContainerError::Corrupt(e) => write!(f, "payload is corrupt: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ContainerError {}
+
+pub fn is_container(payload: &[u8]) -> bool {
+    payload.starts_with(MAGIC)
+}
+
+fn truncate(text: &str, max: usize) -> &str {
+    let mut end = text.len().min(max);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Accepts plain file names only: no separators, drive prefixes, `.` or `..`, or control
+/// characters, and at most [`MAX_NAME`] bytes.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(format!("{:?} is not a file name", name));
+    }
+    if name.len() > MAX_NAME {
+        return Err(format!("file name is {} bytes long, at most {} are allowed", name.len(), MAX_NAME));
+    }
+    if name.chars().any(|c| matches!(c, '/' | '\\' | ':') || c.is_control()) {
+        return Err(format!("file name {:?} must not contain path separators or control characters", name));
+    }
+    Ok(())
+}
+
+/// Guesses the MIME type from the leading bytes, falling back to the file extension.
+pub fn sniff_mime(name: &str, content: &[u8]) -> String {
+    let signatures: [(&[u8], &str); 8] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"RIFF", "audio/wav"),
+    ];
+    if let Some((_, mime)) = signatures.iter().find(|(magic, _)| content.starts_with(magic)) {
+        return mime.to_string();
+    }
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    let by_extension = match extension.as_deref() {
+        Some("txt") => "text/plain",
+        Some("json") => "application/json",
+        Some("html") | Some("htm") => "text/html",
+        Some("csv") => "text/csv",
+        _ if std::str::from_utf8(content).is_ok() => "text/plain",
+        _ => "application/octet-stream",
+    };
+    by_extension.to_string()
+}
+
+/// Bytes a container adds to `metadata` and content, with room for the longest name and MIME type.
+pub fn overhead(metadata: &BTreeMap<String, String>) -> usize {
+    let entries: usize = metadata.iter().map(|(k, v)| 4 + k.len() + v.len()).sum();
+    MAGIC.len() + 1 + 2 + MAX_NAME + 2 + MAX_MIME + 2 + entries + 8 + HASH_LEN
+}
+
+fn put_str(buffer: &mut Vec<u8>, text: &str) {
+    buffer.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(text.as_bytes());
+}
+
+pub fn pack(file: &HiddenFile) -> Result<Vec<u8>, String> {
+    if file.metadata.len() > u16::MAX as usize
+        || file.metadata.iter().any(|(k, v)| k.len() > u16::MAX as usize || v.len() > u16::MAX as usize)
+    {
+        return Err("container metadata is too large".to_string());
+    }
+    check_name(&file.name)?;
+    let mut buffer = MAGIC.to_vec();
+    buffer.push(VERSION);
+    put_str(&mut buffer, &file.name);
+    put_str(&mut buffer, truncate(&file.mime, MAX_MIME));
+    buffer.extend_from_slice(&(file.metadata.len() as u16).to_be_bytes());
+    for (key, value) in &file.metadata {
+        put_str(&mut buffer, key);
+        put_str(&mut buffer, value);
+    }
+    buffer.extend_from_slice(&(file.content.len() as u64).to_be_bytes());
+    buffer.extend_from_slice(&Sha256::digest(&file.content));
+    buffer.extend_from_slice(&file.content);
+    Ok(buffer)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize, what: &str) -> Result<&'a [u8], ContainerError> {
+        if self.bytes.len() < len {
+            return Err(ContainerError::Corrupt(format!("{} is truncated", what)));
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self, what: &str) -> Result<usize, ContainerError> {
+        Ok(u16::from_be_bytes(self.take(2, what)?.try_into().unwrap()) as usize)
+    }
+
+    fn string(&mut self, what: &str) -> Result<String, ContainerError> {
+        let len = self.u16(what)?;
+        String::from_utf8(self.take(len, what)?.to_vec())
+            .map_err(|_| ContainerError::Corrupt(format!("{} is not UTF-8", what)))
+    }
+}
+
+pub fn unpack(payload: &[u8]) -> Result<HiddenFile, ContainerError> {
+    if !is_container(payload) {
+        return Err(ContainerError::NoPayload);
+    }
+    let mut reader = Reader { bytes: &payload[MAGIC.len()..] };
+    let version = reader.take(1, "version")?[0];
+    if version != VERSION {
+        return Err(ContainerError::UnsupportedVersion(version));
+    }
+    let name = reader.string("file name")?;
+    check_name(&name).map_err(ContainerError::Corrupt)?;
+    let mime = reader.string("MIME type")?;
+    let mut metadata = BTreeMap::new();
+    for _ in 0..reader.u16("metadata")? {
+        let key = reader.string("metadata key")?;
+        metadata.insert(key, reader.string("metadata value")?);
+    }
+    let len = u64::from_be_bytes(reader.take(8, "length")?.try_into().unwrap());
+    let hash = reader.take(HASH_LEN, "content hash")?;
+    if reader.bytes.len() as u64 != len {
+        return Err(ContainerError::Corrupt(format!("expected {} bytes of content, found {}", len, reader.bytes.len())));
+    }
+    if Sha256::digest(reader.bytes).as_slice() != hash {
+        return Err(ContainerError::Corrupt("content does not match its hash".to_string()));
+    }
+    Ok(HiddenFile { name, mime, metadata, content: reader.bytes.to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file() -> HiddenFile {
+        HiddenFile {
+            name: "holiday.jpg".to_string(),
+            mime: "image/jpeg".to_string(),
+            metadata: [("taken".to_string(), "2024-06-01".to_string())].into_iter().collect(),
+            content: b"\xff\xd8\xff not really a jpeg".to_vec(),
+        }
+    }
+
+    #[test]
+    fn files_round_trip_exactly() {
+        let packed = pack(&file()).unwrap();
+        assert_eq!(unpack(&packed).unwrap(), file());
+        assert!(packed.len() <= file().content.len() + overhead(&file().metadata));
+    }
+
+    #[test]
+    fn missing_and_damaged_payloads_are_told_apart() {
+        assert_eq!(unpack(b"just some bytes"), Err(ContainerError::NoPayload));
+
+        let mut packed = pack(&file()).unwrap();
+        let last = packed.len() - 1;
+        packed[last] ^= 1;
+        assert!(matches!(unpack(&packed), Err(ContainerError::Corrupt(_))));
+        assert!(matches!(unpack(&packed[..20]), Err(ContainerError::Corrupt(_))));
+        packed[4] = 9;
+        assert_eq!(unpack(&packed), Err(ContainerError::UnsupportedVersion(9)));
+    }
+
+    #[test]
+    fn names_cannot_escape_the_output_directory() {
+        for name in ["../../etc/passwd", "/etc/passwd", "..", ".", "", "a\\b", "C:evil", "x\0y"] {
+            let file = HiddenFile { name: name.to_string(), ..file() };
+            assert!(pack(&file).is_err(), "{:?}", name);
+        }
+        assert!(pack(&HiddenFile { name: "..hidden".to_string(), ..file() }).is_ok());
+        assert!(pack(&HiddenFile { name: "é".repeat(MAX_NAME / 2), ..file() }).is_ok());
+        assert!(pack(&HiddenFile { name: format!("{}.jpg", "é".repeat(MAX_NAME / 2)), ..file() }).is_err());
+
+        // A container written by hand rather than by `pack`
+        let mut forged = pack(&HiddenFile { name: "..%passwd".to_string(), ..file() }).unwrap();
+        let at = forged.iter().position(|&b| b == b'%').unwrap();
+        forged[at] = b'/';
+        assert!(matches!(unpack(&forged), Err(ContainerError::Corrupt(_))));
+
+        let dir = std::env::temp_dir().join(format!("p2p-container-{}", rand::random::<u64>()));
+        let evil = HiddenFile { name: "../escaped".to_string(), ..file() };
+        assert_eq!(evil.save_in(&dir).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert!(!dir.parent().unwrap().join("escaped").exists());
+        assert_eq!(file().save_in(&dir).unwrap(), dir.join("holiday.jpg"));
+        assert_eq!(fs::read(dir.join("holiday.jpg")).unwrap(), file().content);
+    }
+
+    #[test]
+    fn mime_types_are_sniffed() {
+        assert_eq!(sniff_mime("x.bin", b"\x89PNG\r\n\x1a\n...."), "image/png");
+        assert_eq!(sniff_mime("notes.txt", b"\xff\xfe"), "text/plain");
+        assert_eq!(sniff_mime("blob", &[0xff, 0x00, 0xfe]), "application/octet-stream");
+        assert_eq!(truncate("ééé", 3), "é");
+    }
+}
@@ -0,0 +1,196 @@
+//! Optional authenticated encryption of payloads before they are embedded.
+//!
+//! A sealed payload is `P2PE`, a format version, the key derivation used, the salt (for
+//! passwords), a random 96-bit nonce and the ChaCha20-Poly1305 ciphertext. The header is
+//! authenticated together with the ciphertext, so tampering with either is detected.
+
+use std::fmt;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+const MAGIC: &[u8; 4] = b"P2PE";
+pub const VERSION: u8 = 1;
+const KDF_NONE: u8 = 0;
+const KDF_ARGON2ID: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+/// Where the encryption key comes from.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encryption {
+    /// A password, stretched with Argon2id and a random salt.
+    Password(String),
+    /// A raw 256-bit key.
+    Key([u8; 32]),
+}
+
+// Keys and passwords stay out of logs.
+impl fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encryption::Password(_) => write!(f, "Password(..)"),
+            Encryption::Key(_) => write!(f, "Key(..)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// The payload is not a sealed payload.
+    NotEncrypted,
+    /// The payload is sealed and no key or password was given.
+    KeyRequired,
+    /// The payload is sealed, but not with this key or password.
+    WrongKey,
+    Malformed(String),
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::NotEncrypted => write!(f, "payload is not encrypted"),
+            CryptoError::KeyRequired => write!(f, "payload is encrypted, a key or password is required"),
+            CryptoError::WrongKey => write!(f, "wrong key or password, or the payload was tampered with"),
+            CryptoError::Malformed(e) => write!(f, "malformed encrypted payload: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {}
+
+fn header_len(kdf: u8) -> usize {
+    MAGIC.len() + 2 + if kdf == KDF_ARGON2ID { SALT_LEN } else { 0 } + NONCE_LEN
+}
+
+/// Bytes sealing adds on top of the plaintext.
+pub fn overhead(encryption: &Encryption) -> usize {
+    let kdf = match encryption {
+        Encryption::Password(_) => KDF_ARGON2ID,
+        Encryption::Key(_) => KDF_NONE,
+    };
+    header_len(kdf) + TAG_LEN
+}
+
+pub fn is_sealed(payload: &[u8]) -> bool {
+    payload.starts_with(MAGIC)
+}
+
+fn derive_key(encryption: &Encryption, salt: &[u8]) -> Result<Key, CryptoError> {
+    match encryption {
+        Encryption::Key(key) => Ok(*Key::from_slice(key)),
+        Encryption::Password(password) => {
+            let mut key = [0u8; 32];
+            Argon2::default()
+                .hash_password_into(password.as_bytes(), salt, &mut key)
+                .map_err(|e| CryptoError::Malformed(e.to_string()))?;
+            Ok(*Key::from_slice(&key))
+        }
+    }
+}
+
+pub fn seal(plaintext: &[u8], encryption: &Encryption) -> Result<Vec<u8>, CryptoError> {
+    let mut rng = rand::thread_rng();
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    let salt = match encryption {
+        Encryption::Password(_) => {
+            header.push(KDF_ARGON2ID);
+            let mut salt = [0u8; SALT_LEN];
+            rng.fill_bytes(&mut salt);
+            header.extend_from_slice(&salt);
+            salt.to_vec()
+        }
+        Encryption::Key(_) => {
+            header.push(KDF_NONE);
+            Vec::new()
+        }
+    };
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    header.extend_from_slice(&nonce);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(encryption, &salt)?);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &header })
+        .map_err(|e| CryptoError::Malformed(e.to_string()))?;
+    header.extend_from_slice(&ciphertext);
+    Ok(header)
+}
+
+pub fn open(sealed: &[u8], encryption: &Encryption) -> Result<Vec<u8>, CryptoError> {
+    if !is_sealed(sealed) {
+        return Err(CryptoError::NotEncrypted);
+    }
+    if sealed.len() < MAGIC.len() + 2 {
+        return Err(CryptoError::Malformed("truncated header".to_string()));
+    }
+    if sealed[MAGIC.len()] != VERSION {
+        return Err(CryptoError::Malformed(format!("unsupported version {}", sealed[MAGIC.len()])));
+    }
+    let kdf = sealed[MAGIC.len() + 1];
+    if kdf != KDF_NONE && kdf != KDF_ARGON2ID {
+        return Err(CryptoError::Malformed(format!("unknown key derivation {}", kdf)));
+    }
+    let header_len = header_len(kdf);
+    if sealed.len() < header_len + TAG_LEN {
+        return Err(CryptoError::Malformed("truncated payload".to_string()));
+    }
+    // A password cannot open a payload sealed with a raw key and vice versa
+    let expected_kdf = match encryption {
+        Encryption::Password(_) => KDF_ARGON2ID,
+        Encryption::Key(_) => KDF_NONE,
+    };
+    if kdf != expected_kdf {
+        return Err(CryptoError::WrongKey);
+    }
+
+    let (header, ciphertext) = sealed.split_at(header_len);
+    let salt = &header[MAGIC.len() + 2..header_len - NONCE_LEN];
+    let nonce = &header[header_len - NONCE_LEN..];
+    let cipher = ChaCha20Poly1305::new(&derive_key(encryption, salt)?);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| CryptoError::WrongKey)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_with_the_right_key() {
+        for encryption in [Encryption::Password("hunter2".into()), Encryption::Key([7; 32])] {
+            let sealed = seal(b"secret image", &encryption).unwrap();
+            assert_eq!(sealed.len(), b"secret image".len() + overhead(&encryption));
+            assert_eq!(open(&sealed, &encryption).unwrap(), b"secret image");
+        }
+    }
+
+    #[test]
+    fn wrong_keys_are_reported_as_such() {
+        let sealed = seal(b"secret image", &Encryption::Password("hunter2".into())).unwrap();
+        assert_eq!(open(&sealed, &Encryption::Password("hunter3".into())), Err(CryptoError::WrongKey));
+        assert_eq!(open(&sealed, &Encryption::Key([7; 32])), Err(CryptoError::WrongKey));
+        assert_eq!(open(b"plain bytes", &Encryption::Key([7; 32])), Err(CryptoError::NotEncrypted));
+    }
+
+    #[test]
+    fn nonces_are_fresh_and_tampering_is_detected() {
+        let key = Encryption::Key([1; 32]);
+        let a = seal(b"same", &key).unwrap();
+        let b = seal(b"same", &key).unwrap();
+        assert_ne!(a, b);
+
+        let mut tampered = a.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(open(&tampered, &key), Err(CryptoError::WrongKey));
+        let mut tampered = a;
+        tampered[8] ^= 1; // inside the nonce
+        assert_eq!(open(&tampered, &key), Err(CryptoError::WrongKey));
+    }
+}
@@ -0,0 +1,399 @@
+//! Frequency-domain embedding that survives JPEG compression.
+//!
+//! The carrier is split into 8x8 blocks of luminance, the way a JPEG encoder sees it. Each
+//! block carries three bits in mid-frequency DCT coefficients, which JPEG keeps. A bit is the
+//! parity of the coefficient quantized with a step of twice the JPEG quantizer at the
+//! configured quality, so recompressing at that quality or better rounds every coefficient
+//! back to the value that was written.
+//!
+//! Only luminance changes, by the same amount in red, green and blue; the result is refused
+//! if any channel would move by more than `max_pixel_diff`. Stego images are written as JPEG
+//! at the configured quality, so both ends must use the same `compression_quality`.
+//!
+//! Blocks are independent, so coefficients are computed and corrected a row of blocks at a
+//! time on the current rayon pool.
+
+use std::f32::consts::PI;
+
+use image::jpeg::JPEGEncoder;
+use image::{ColorType, DynamicImage, RgbaImage};
+use rayon::prelude::*;
+
+use crate::stego;
+
+/// Standard JPEG luminance quantization table, row by row.
+const LUMA_QUANTIZATION: [u32; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61,
+    12, 12, 14, 19, 26, 58, 60, 55,
+    14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62,
+    18, 22, 37, 56, 68, 109, 103, 77,
+    24, 35, 55, 64, 81, 104, 113, 92,
+    49, 64, 78, 87, 103, 121, 120, 101,
+    72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// Coefficients used in every block, as (row, column) of the 8x8 DCT.
+const COEFFICIENTS: [(usize, usize); 3] = [(1, 2), (2, 1), (2, 2)];
+/// Smallest quantization step; below it rounding in the colour conversion could flip bits.
+const MIN_STEP: f32 = 6.0;
+const MAGIC: [u8; 3] = [0xd7, 0xc7, 0x5a];
+const LEN_BYTES: usize = 4;
+// The magic and length, written three times over, see [`stego::repeat_header`]
+const HEADER_BYTES: usize = (MAGIC.len() + LEN_BYTES) * 3;
+/// Rounding to whole pixel values can push a coefficient off its target; a few more passes
+/// pull it back.
+const PASSES: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DctParams {
+    /// JPEG quality (1-100) the payload has to survive and stego images are written at.
+    pub quality: u8,
+    /// Largest change allowed to any colour channel of any pixel.
+    pub max_pixel_diff: u8,
+}
+
+impl DctParams {
+    fn step(&self, (row, column): (usize, usize)) -> f32 {
+        // Scaling of the libjpeg reference encoder, which the image crate uses as well
+        let quality = u32::from(self.quality.clamp(1, 100));
+        let scale = if quality < 50 { 5000 / quality } else { 200 - quality * 2 };
+        let quantizer = ((LUMA_QUANTIZATION[row * 8 + column] * scale + 50) / 100).clamp(1, 255);
+        (2 * quantizer) as f32
+    }
+
+    fn steps(&self) -> [f32; 3] {
+        COEFFICIENTS.map(|c| self.step(c).max(MIN_STEP))
+    }
+}
+
+/// Number of payload bytes a `width` x `height` carrier can hold.
+pub fn capacity(width: u32, height: u32) -> usize {
+    let blocks = (width / 8) as usize * (height / 8) as usize;
+    (blocks * COEFFICIENTS.len() / 8).saturating_sub(HEADER_BYTES)
+}
+
+// basis[k][n]: weight of sample n in coefficient k of the orthonormal 8 point DCT-II, which
+// is the transform JPEG uses
+fn basis() -> [[f32; 8]; 8] {
+    let mut basis = [[0.0; 8]; 8];
+    for (k, row) in basis.iter_mut().enumerate() {
+        let scale = if k == 0 { (1.0f32 / 8.0).sqrt() } else { 0.5 };
+        for (n, weight) in row.iter_mut().enumerate() {
+            *weight = scale * ((2 * n + 1) as f32 * k as f32 * PI / 16.0).cos();
+        }
+    }
+    basis
+}
+
+fn luma(image: &RgbaImage) -> Vec<f32> {
+    let channels: &[u8] = image;
+    channels.par_chunks(4).map(pixel_luma).collect()
+}
+
+fn luma_sequential(image: &RgbaImage) -> Vec<f32> {
+    image.pixels().map(|p| pixel_luma(&p.data)).collect()
+}
+
+fn pixel_luma(p: &[u8]) -> f32 {
+    0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32
+}
+
+struct Blocks {
+    width: usize,
+    per_row: usize,
+    basis: [[f32; 8]; 8],
+}
+
+impl Blocks {
+    fn new(width: u32) -> Self {
+        Self { width: width as usize, per_row: width as usize / 8, basis: basis() }
+    }
+
+    // Top-left pixel of the block holding bit `bit`, and the coefficient it goes into
+    fn locate(&self, bit: usize) -> (usize, usize, (usize, usize)) {
+        let block = bit / COEFFICIENTS.len();
+        (block % self.per_row * 8, block / self.per_row * 8, COEFFICIENTS[bit % COEFFICIENTS.len()])
+    }
+
+    fn coefficient(&self, luma: &[f32], bit: usize) -> f32 {
+        let (x0, y0, (row, column)) = self.locate(bit);
+        let mut sum = 0.0;
+        for y in 0..8 {
+            for x in 0..8 {
+                sum += luma[(y0 + y) * self.width + x0 + x] * self.basis[row][y] * self.basis[column][x];
+            }
+        }
+        sum
+    }
+
+    // Bits held by each row of blocks
+    fn bits_per_row(&self) -> usize {
+        self.per_row * COEFFICIENTS.len()
+    }
+
+    // Spreads a change of one coefficient over the pixels of its block, in `band`, the deltas
+    // of the eight pixel rows the block is in
+    fn add(&self, band: &mut [f32], bit: usize, delta: f32) {
+        let (x0, _, (row, column)) = self.locate(bit);
+        for y in 0..8 {
+            for x in 0..8 {
+                band[y * self.width + x0 + x] += delta * self.basis[row][y] * self.basis[column][x];
+            }
+        }
+    }
+}
+
+fn read_bit(coefficient: f32, step: f32) -> u8 {
+    ((coefficient / step).round() as i64).rem_euclid(2) as u8
+}
+
+// The multiple of `step` nearest to `coefficient` whose parity is `bit`
+fn target(coefficient: f32, step: f32, bit: u8) -> f32 {
+    let q = (coefficient / step).round();
+    if (q as i64).rem_euclid(2) as u8 == bit {
+        q * step
+    } else if coefficient / step > q {
+        (q + 1.0) * step
+    } else {
+        (q - 1.0) * step
+    }
+}
+
+// The carrier as RGBA and the bits to write into it, if `payload` fits
+fn prepare(carrier: &DynamicImage, payload: &[u8]) -> Result<(RgbaImage, Vec<u8>), String> {
+    let original = carrier.to_rgba();
+    let available = capacity(original.width(), original.height());
+    if payload.len() > available {
+        return Err(format!(
+            "payload of {} bytes does not fit into a {}x{} carrier, which holds {} bytes in DCT mode",
+            payload.len(), original.width(), original.height(), available
+        ));
+    }
+    let header: Vec<u8> = MAGIC.iter().copied().chain((payload.len() as u32).to_be_bytes()).collect();
+    let bits = stego::repeat_header(&header).into_iter().chain(payload.iter().copied()).flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1)).collect();
+    Ok((original, bits))
+}
+
+// `image`, unless it strays too far from `original`
+fn within_bounds(original: &RgbaImage, image: RgbaImage, params: &DctParams) -> Result<RgbaImage, String> {
+    let max_diff = original.pixels().zip(image.pixels())
+        .flat_map(|(a, b)| (0..3).map(move |c| (a[c] as i16 - b[c] as i16).unsigned_abs()))
+        .max()
+        .unwrap_or(0);
+    if max_diff > params.max_pixel_diff as u16 {
+        return Err(format!(
+            "DCT embedding at quality {} changes pixels by up to {}, more than the allowed {}",
+            params.quality, max_diff, params.max_pixel_diff
+        ));
+    }
+    Ok(image)
+}
+
+fn shift(pixel: &mut [u8], delta: f32) {
+    for channel in &mut pixel[..3] {
+        *channel = (*channel as f32 + delta).round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Hides `payload` in a copy of `carrier`.
+pub fn embed(carrier: &DynamicImage, payload: &[u8], params: &DctParams) -> Result<RgbaImage, String> {
+    let (original, bits) = prepare(carrier, payload)?;
+    let blocks = Blocks::new(original.width());
+    let steps = params.steps();
+    let step = |bit: usize| steps[bit % COEFFICIENTS.len()];
+
+    let luma_before = luma(&original);
+    let targets: Vec<f32> = bits.par_iter().enumerate()
+        .map(|(i, &bit)| target(blocks.coefficient(&luma_before, i), step(i), bit))
+        .collect();
+
+    let mut image = original.clone();
+    let band = (8 * blocks.width).max(1);
+    for _ in 0..PASSES {
+        let luma = luma(&image);
+        let mut deltas = vec![0.0f32; luma.len()];
+        let off_target = deltas.par_chunks_mut(band).enumerate().map(|(block_row, band)| {
+            let first = block_row * blocks.bits_per_row();
+            let mut off_target = false;
+            for (i, target) in targets.iter().enumerate().skip(first).take(blocks.bits_per_row()) {
+                let delta = target - blocks.coefficient(&luma, i);
+                if delta.abs() > 0.5 {
+                    blocks.add(band, i, delta);
+                    off_target = true;
+                }
+            }
+            off_target
+        });
+        if !off_target.reduce(|| false, |a, b| a || b) {
+            break;
+        }
+        let channels: &mut [u8] = &mut image;
+        channels.par_chunks_mut(4).zip(deltas).for_each(|(pixel, delta)| shift(pixel, delta));
+    }
+    within_bounds(&original, image, params)
+}
+
+/// [`embed`] the way it ran before it was spread over the rayon pool, one bit after the
+/// other on the calling thread. Kept as the baseline for benchmarks and tests.
+#[doc(hidden)]
+pub fn embed_sequential(carrier: &DynamicImage, payload: &[u8], params: &DctParams) -> Result<RgbaImage, String> {
+    let (original, bits) = prepare(carrier, payload)?;
+    let blocks = Blocks::new(original.width());
+    let steps = params.steps();
+    let step = |bit: usize| steps[bit % COEFFICIENTS.len()];
+
+    let luma_before = luma_sequential(&original);
+    let targets: Vec<f32> = bits.iter().enumerate()
+        .map(|(i, &bit)| target(blocks.coefficient(&luma_before, i), step(i), bit))
+        .collect();
+
+    let mut image = original.clone();
+    for _ in 0..PASSES {
+        let luma = luma_sequential(&image);
+        let mut deltas = vec![0.0f32; luma.len()];
+        let mut off_target = false;
+        for (i, target) in targets.iter().enumerate() {
+            let delta = target - blocks.coefficient(&luma, i);
+            if delta.abs() > 0.5 {
+                let (_, y0, _) = blocks.locate(i);
+                blocks.add(&mut deltas[y0 * blocks.width..], i, delta);
+                off_target = true;
+            }
+        }
+        if !off_target {
+            break;
+        }
+        for (pixel, delta) in image.pixels_mut().zip(deltas) {
+            shift(&mut pixel.data, delta);
+        }
+    }
+    within_bounds(&original, image, params)
+}
+
+/// Reads back a payload written by [`embed`].
+pub fn extract(stego: &DynamicImage, params: &DctParams) -> Result<Vec<u8>, String> {
+    peek(stego, params, usize::MAX).map(|(_, payload)| payload)
+}
+
+/// [`extract`] on the calling thread alone, the baseline to [`embed_sequential`].
+#[doc(hidden)]
+pub fn extract_sequential(stego: &DynamicImage, params: &DctParams) -> Result<Vec<u8>, String> {
+    read(stego, params, usize::MAX, false).map(|(_, payload)| payload)
+}
+
+/// Reads the declared length of a payload written by [`embed`] and at most `limit` of its
+/// leading bytes.
+pub fn peek(stego: &DynamicImage, params: &DctParams, limit: usize) -> Result<(usize, Vec<u8>), String> {
+    read(stego, params, limit, true)
+}
+
+fn read(stego: &DynamicImage, params: &DctParams, limit: usize, parallel: bool) -> Result<(usize, Vec<u8>), String> {
+    let image = stego.to_rgba();
+    let available = capacity(image.width(), image.height());
+    if available == 0 {
+        return Err("image is too small to hold a DCT payload".to_string());
+    }
+    let luma = if parallel { luma(&image) } else { luma_sequential(&image) };
+    let blocks = Blocks::new(image.width());
+    let steps = params.steps();
+    let byte = |n: usize| (0..8).fold(0u8, |byte, i| {
+        let bit = n * 8 + i;
+        byte << 1 | read_bit(blocks.coefficient(&luma, bit), steps[bit % COEFFICIENTS.len()])
+    });
+
+    let header = stego::vote_header(&(0..HEADER_BYTES).map(byte).collect::<Vec<u8>>());
+    let (magic, len) = header.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return Err("image does not contain a DCT payload".to_string());
+    }
+    let len = u32::from_be_bytes(len.try_into().unwrap()) as usize;
+    if len > available {
+        return Err("image does not contain a DCT payload".to_string());
+    }
+    let bytes = HEADER_BYTES..HEADER_BYTES + len.min(limit);
+    Ok((len, if parallel { bytes.into_par_iter().map(byte).collect() } else { bytes.map(byte).collect() }))
+}
+
+/// Hides `payload` in `carrier` and returns the stego image as JPEG at the configured quality.
+/// The result is decoded again to make sure the payload survived.
+pub fn hide(carrier: &DynamicImage, payload: &[u8], params: &DctParams) -> Result<Vec<u8>, String> {
+    let stego = DynamicImage::ImageRgba8(embed(carrier, payload, params)?).to_rgb();
+    let mut buffer = Vec::new();
+    JPEGEncoder::new_with_quality(&mut buffer, params.quality.clamp(1, 100))
+        .encode(&stego, stego.width(), stego.height(), ColorType::RGB(8))
+        .map_err(|e| e.to_string())?;
+
+    let decoded = image::load_from_memory(&buffer).map_err(|e| e.to_string())?;
+    if extract(&decoded, params).ok().as_deref() != Some(payload) {
+        return Err("carrier is too bright or too dark in places to hold the payload in DCT mode".to_string());
+    }
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageOutputFormat, Rgba};
+
+    const PARAMS: DctParams = DctParams { quality: 75, max_pixel_diff: 10 };
+
+    fn carrier(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(60 + x % 97) as u8, (80 + (x + y) % 71) as u8, (70 + y % 113) as u8, 255])
+        }))
+    }
+
+    fn recompress(jpeg: &[u8], quality: u8) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        image::load_from_memory(jpeg).unwrap().write_to(&mut buffer, ImageOutputFormat::JPEG(quality)).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn payload_survives_recompression() {
+        let payload: Vec<u8> = (0..=255).cycle().take(capacity(128, 96)).collect();
+        let stego = hide(&carrier(128, 96), &payload, &PARAMS).unwrap();
+        for quality in [75, 90, 100] {
+            let resaved = recompress(&stego, quality);
+            assert_eq!(extract(&image::load_from_memory(&resaved).unwrap(), &PARAMS).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn distortion_is_bounded() {
+        let original = carrier(128, 128);
+        let stego = embed(&original, &[0xa5; 70], &PARAMS).unwrap();
+        for (a, b) in original.to_rgba().pixels().zip(stego.pixels()) {
+            for c in 0..3 {
+                assert!((a[c] as i16 - b[c] as i16).abs() <= PARAMS.max_pixel_diff as i16);
+            }
+        }
+        let strict = DctParams { quality: 20, max_pixel_diff: 2 };
+        assert!(embed(&original, &[0xa5; 70], &strict).is_err());
+    }
+
+    #[test]
+    fn a_flipped_bit_in_the_header_is_outvoted() {
+        let mut stego = embed(&carrier(128, 96), b"payload", &PARAMS).unwrap();
+        let (blocks, steps) = (Blocks::new(stego.width()), PARAMS.steps());
+        // Move the coefficient of the last length bit of the first copy by one step
+        let bit = 8 * (MAGIC.len() + LEN_BYTES) - 1;
+        let step = steps[bit % COEFFICIENTS.len()];
+        let before = read_bit(blocks.coefficient(&luma(&stego), bit), step);
+        let (_, y0, _) = blocks.locate(bit);
+        let mut deltas = vec![0.0f32; stego.pixels().len()];
+        blocks.add(&mut deltas[y0 * blocks.width..], bit, step);
+        for (pixel, delta) in stego.pixels_mut().zip(deltas) {
+            shift(&mut pixel.data, delta);
+        }
+        assert_ne!(read_bit(blocks.coefficient(&luma(&stego), bit), step), before);
+        assert_eq!(extract(&DynamicImage::ImageRgba8(stego), &PARAMS).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn plain_images_hold_no_payload() {
+        assert!(extract(&carrier(64, 64), &PARAMS).is_err());
+        assert!(embed(&carrier(64, 64), &vec![0; capacity(64, 64) + 1], &PARAMS).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Serialize, Deserialize};
+
+// Messages exchanged between nodes on the leader election port. They travel as bincode,
+// so field order and variant order are part of the wire format.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum VoteReason {
+    HighCPULoad,
+    HighMemoryUsage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemMetrics {
+    pub cpu_load: f64,
+    pub memory_usage: f64,
+    pub load_average: f64,
+}
+
+impl Default for SystemMetrics {
+    fn default() -> Self {
+        Self {
+            cpu_load: 50.0,
+            memory_usage: 60.0,
+            load_average: 70.0,
+
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum NodeMessage {
+    Heartbeat {
+        leader_id: u64,
+        metrics: SystemMetrics,
+        candidates: Vec<Candidate>,
+    },
+    NegativeVote {
+        voter_id: u64,
+        reason: VoteReason,
+        metrics: SystemMetrics,
+    },
+    ElectionResult { new_leader_id: u64 },
+    UpdateMetrics(SystemMetrics),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Candidate {
+    pub id: u64,
+    pub metrics: SystemMetrics,
+    pub score: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn election_result_encoding_is_pinned() {
+        let bytes = bincode::serialize(&NodeMessage::ElectionResult { new_leader_id: 3 }).unwrap();
+        assert_eq!(bytes, vec![2, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn negative_vote_encoding_is_pinned() {
+        let msg = NodeMessage::NegativeVote {
+            voter_id: 1,
+            reason: VoteReason::HighMemoryUsage,
+            metrics: SystemMetrics { cpu_load: 0.0, memory_usage: 0.0, load_average: 0.0 },
+        };
+        let bytes = bincode::serialize(&msg).unwrap();
+        let mut expected = vec![1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+        expected.extend_from_slice(&[0; 24]);
+        assert_eq!(bytes, expected);
+    }
+}
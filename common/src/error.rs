@@ -0,0 +1,112 @@
+//! Errors of the steganography service. They cross the wire as they are, so clients can
+//! tell a bad request from a transient failure without parsing messages.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::container::ContainerError;
+use crate::crypto::CryptoError;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StegError {
+    /// A stego image or carrier could not be decoded.
+    InvalidImage(String),
+    /// The call does not make sense for this image, e.g. `decode` on a policy protected one.
+    InvalidRequest(String),
+    PayloadTooLarge { payload: u64, capacity: u64 },
+    /// The image carries nothing that was hidden by this service.
+    NoPayload,
+    /// Something was hidden, but it is damaged or cut short.
+    CorruptPayload(String),
+    /// Hidden by a newer release.
+    UnsupportedVersion(u8),
+    /// No carrier matches the request, or the library is empty.
+    CarrierUnavailable(String),
+    /// The carrier is unsuitable for the mode, e.g. DCT would exceed `max_pixel_diff`.
+    EmbedFailed(String),
+    KeyRequired,
+    WrongKey,
+    /// The user lacks the permission or has used up the quota.
+    Unauthorized(String),
+    /// Refused by the access policy embedded in the image.
+    AccessDenied(String),
+    /// The node is not set up for this, e.g. it has no cluster key.
+    Unsupported(String),
+    Io(String),
+    Internal(String),
+    /// A stego or carrier WAV file could not be decoded.
+    InvalidAudio(String),
+}
+
+impl fmt::Display for StegError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StegError::InvalidImage(e) => write!(f, "invalid image: {}", e),
+            StegError::InvalidRequest(e) => write!(f, "invalid request: {}", e),
+            StegError::PayloadTooLarge { payload, capacity } => {
+                write!(f, "payload of {} bytes does not fit, the carrier holds {} bytes", payload, capacity)
+            }
+            StegError::NoPayload => write!(f, "image does not contain a payload"),
+            StegError::CorruptPayload(e) => write!(f, "payload is corrupt: {}", e),
+            StegError::UnsupportedVersion(v) => write!(f, "payload container version {} is not supported", v),
+            StegError::CarrierUnavailable(e) => write!(f, "carrier unavailable: {}", e),
+            StegError::EmbedFailed(e) => write!(f, "embedding failed: {}", e),
+            StegError::KeyRequired => write!(f, "payload is encrypted, a key or password is required"),
+            StegError::WrongKey => write!(f, "wrong key or password, or the payload was tampered with"),
+            StegError::Unauthorized(e) => write!(f, "unauthorized: {}", e),
+            StegError::AccessDenied(e) => write!(f, "access denied: {}", e),
+            StegError::Unsupported(e) => write!(f, "not supported: {}", e),
+            StegError::Io(e) => write!(f, "I/O error: {}", e),
+            StegError::Internal(e) => write!(f, "internal error: {}", e),
+            StegError::InvalidAudio(e) => write!(f, "invalid audio: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StegError {}
+
+impl From<ContainerError> for StegError {
+    fn from(e: ContainerError) -> Self {
+        match e {
+            ContainerError::NoPayload => StegError::NoPayload,
+            ContainerError::UnsupportedVersion(v) => StegError::UnsupportedVersion(v),
+            ContainerError::Corrupt(e) => StegError::CorruptPayload(e),
+        }
+    }
+}
+
+impl From<CryptoError> for StegError {
+    fn from(e: CryptoError) -> Self {
+        match e {
+            CryptoError::KeyRequired => StegError::KeyRequired,
+            CryptoError::WrongKey => StegError::WrongKey,
+            CryptoError::NotEncrypted | CryptoError::Malformed(_) => StegError::CorruptPayload(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for StegError {
+    fn from(e: std::io::Error) -> Self {
+        StegError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_survive_the_wire_and_keep_their_kind() {
+        for error in [
+            StegError::PayloadTooLarge { payload: 10, capacity: 4 },
+            StegError::NoPayload,
+            StegError::Unauthorized("bob lacks the encode permission".to_string()),
+        ] {
+            let bytes = bincode::serialize(&error).unwrap();
+            assert_eq!(bincode::deserialize::<StegError>(&bytes).unwrap(), error);
+        }
+        assert_eq!(StegError::from(ContainerError::UnsupportedVersion(2)), StegError::UnsupportedVersion(2));
+        assert_eq!(StegError::from(CryptoError::WrongKey), StegError::WrongKey);
+    }
+}
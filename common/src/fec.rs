@@ -0,0 +1,207 @@
+//! Forward error correction for embedded payloads.
+//!
+//! A handful of flipped bits, e.g. from an editor touching up a corner of a stego image, would
+//! otherwise break every layer of the payload. With error correction the payload is cut into
+//! up to 128 data shards and Reed-Solomon parity shards are added. Every shard carries a short
+//! checksum, so damaged shards are found and rebuilt from the others, as long as no more of
+//! them are damaged than there are parity shards.
+//!
+//! ```text
+//! header: P2PF | version (1) | redundancy (1) | data shards (1) | shard length (u32 BE)
+//!              | payload length (u32 BE) | checksum (4)
+//! header | header | header | for every shard: checksum (4) | shard
+//! ```
+//!
+//! The header is written three times and the first intact copy is used. Error correction is
+//! the outermost layer, so the provenance signature is checked on the repaired payload. The
+//! length the embedding modes put in front of it is kept three times over as well, see
+//! [`crate::stego`].
+
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use sha2::{Digest, Sha256};
+
+const MAGIC: &[u8; 4] = b"P2PF";
+pub const VERSION: u8 = 1;
+const CHECKSUM_LEN: usize = 4;
+const HEADER_LEN: usize = MAGIC.len() + 3 + 4 + 4 + CHECKSUM_LEN;
+const HEADER_COPIES: usize = 3;
+// Payloads are cut into shards of about this size, up to the shard limit
+const SHARD_TARGET: usize = 32;
+const MAX_DATA_SHARDS: usize = 128;
+/// Most parity that can be asked for, as a percentage of the data.
+pub const MAX_REDUNDANCY: u8 = 100;
+
+/// A payload with its error correction stripped off.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovered {
+    pub payload: Vec<u8>,
+    /// Redundancy the payload was protected with, `None` if it had no error correction.
+    pub redundancy: Option<u8>,
+    /// Bytes that were wrong and have been repaired.
+    pub corrected_bytes: usize,
+    /// Shards that failed their checksum; the payload was rebuilt without them.
+    pub repaired_shards: usize,
+}
+
+// Data shards, parity shards and shard length for a payload of `len` bytes
+fn layout(len: usize, redundancy: u8) -> (usize, usize, usize) {
+    let data = len.div_ceil(SHARD_TARGET).clamp(1, MAX_DATA_SHARDS);
+    let parity = (data * usize::from(redundancy)).div_ceil(100).max(1);
+    (data, parity, len.div_ceil(data).max(1))
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    Sha256::digest(bytes)[..CHECKSUM_LEN].try_into().unwrap()
+}
+
+pub fn check_redundancy(redundancy: u8) -> Result<(), String> {
+    if redundancy == 0 || redundancy > MAX_REDUNDANCY {
+        return Err(format!("redundancy must be between 1 and {} percent", MAX_REDUNDANCY));
+    }
+    Ok(())
+}
+
+/// Size of a `len` byte payload once protected with `redundancy` percent parity.
+pub fn encoded_len(len: usize, redundancy: u8) -> usize {
+    let (data, parity, shard_len) = layout(len, redundancy);
+    HEADER_COPIES * HEADER_LEN + (data + parity) * (CHECKSUM_LEN + shard_len)
+}
+
+pub fn is_protected(payload: &[u8]) -> bool {
+    (0..HEADER_COPIES).any(|copy| payload[(copy * HEADER_LEN).min(payload.len())..].starts_with(MAGIC))
+}
+
+/// Adds `redundancy` percent of parity to `payload`. At 25 percent, a quarter of the shards may
+/// be damaged and the payload still comes back.
+pub fn protect(payload: &[u8], redundancy: u8) -> Result<Vec<u8>, String> {
+    check_redundancy(redundancy)?;
+    let (data, parity, shard_len) = layout(payload.len(), redundancy);
+    let mut shards: Vec<Vec<u8>> = (0..data + parity)
+        .map(|i| {
+            let start = (i * shard_len).min(payload.len());
+            let mut shard = payload[start..(start + shard_len).min(payload.len())].to_vec();
+            shard.resize(shard_len, 0);
+            shard
+        })
+        .collect();
+    ReedSolomon::new(data, parity).and_then(|rs| rs.encode(&mut shards)).map_err(|e| format!("{:?}", e))?;
+
+    let mut header = MAGIC.to_vec();
+    header.extend_from_slice(&[VERSION, redundancy, data as u8]);
+    header.extend_from_slice(&(shard_len as u32).to_be_bytes());
+    header.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    header.extend_from_slice(&checksum(&header));
+
+    let mut protected = header.repeat(HEADER_COPIES);
+    for shard in &shards {
+        protected.extend_from_slice(&checksum(shard));
+        protected.extend_from_slice(shard);
+    }
+    Ok(protected)
+}
+
+/// Repairs a payload written by [`protect`] and strips the error correction off. Payloads
+/// without error correction are passed through unchanged.
+pub fn recover(payload: Vec<u8>) -> Result<Recovered, String> {
+    if !is_protected(&payload) {
+        return Ok(Recovered { payload, redundancy: None, corrected_bytes: 0, repaired_shards: 0 });
+    }
+    let header = payload
+        .chunks_exact(HEADER_LEN)
+        .take(HEADER_COPIES)
+        .find(|h| h.starts_with(MAGIC) && checksum(&h[..HEADER_LEN - CHECKSUM_LEN])[..] == h[HEADER_LEN - CHECKSUM_LEN..])
+        .ok_or("every copy of the error correction header is damaged")?;
+    if header[4] != VERSION {
+        return Err(format!("error correction version {} is not supported", header[4]));
+    }
+    let redundancy = header[5];
+    let data = usize::from(header[6]);
+    let shard_len = u32::from_be_bytes(header[7..11].try_into().unwrap()) as usize;
+    let len = u32::from_be_bytes(header[11..15].try_into().unwrap()) as usize;
+    check_redundancy(redundancy)?;
+    let (expected_data, parity, expected_shard_len) = layout(len, redundancy);
+    if (expected_data, expected_shard_len) != (data, shard_len) {
+        return Err("error correction header does not match the payload length".to_string());
+    }
+    if payload.len() < encoded_len(len, redundancy) {
+        return Err("payload is truncated".to_string());
+    }
+
+    let received: Vec<&[u8]> = payload[HEADER_COPIES * HEADER_LEN..]
+        .chunks_exact(CHECKSUM_LEN + shard_len)
+        .take(data + parity)
+        .collect();
+    let mut shards: Vec<Option<Vec<u8>>> = received
+        .iter()
+        .map(|chunk| {
+            let (sum, shard) = chunk.split_at(CHECKSUM_LEN);
+            (checksum(shard)[..] == *sum).then(|| shard.to_vec())
+        })
+        .collect();
+    let repaired_shards = shards.iter().filter(|shard| shard.is_none()).count();
+    if repaired_shards > parity {
+        return Err(format!(
+            "{} of {} shards are damaged, error correction can repair {}",
+            repaired_shards, data + parity, parity
+        ));
+    }
+    if repaired_shards > 0 {
+        ReedSolomon::new(data, parity)
+            .and_then(|rs| rs.reconstruct_data(&mut shards))
+            .map_err(|e| format!("{:?}", e))?;
+    }
+
+    let corrected_bytes = shards[..data]
+        .iter()
+        .zip(&received)
+        .map(|(shard, chunk)| {
+            let shard = shard.as_deref().unwrap_or_default();
+            shard.iter().zip(&chunk[CHECKSUM_LEN..]).filter(|(a, b)| a != b).count()
+        })
+        .sum();
+    let mut repaired: Vec<u8> = shards.into_iter().take(data).flatten().flatten().collect();
+    repaired.truncate(len);
+    Ok(Recovered { payload: repaired, redundancy: Some(redundancy), corrected_bytes, repaired_shards })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 + 3) as u8).collect()
+    }
+
+    #[test]
+    fn damaged_shards_are_repaired() {
+        let original = payload(1000);
+        let protected = protect(&original, 25).unwrap();
+        assert_eq!(protected.len(), encoded_len(original.len(), 25));
+        assert_eq!(recover(protected.clone()).unwrap().corrected_bytes, 0);
+
+        // Wreck the first header copy and flip bits spread over seven of the 32 data shards
+        let mut damaged = protected.clone();
+        damaged[0] ^= 0xff;
+        let shards = HEADER_COPIES * HEADER_LEN;
+        for i in 0..7 {
+            damaged[shards + i * 4 * (CHECKSUM_LEN + 32) + CHECKSUM_LEN + 5] ^= 0x10;
+        }
+        let recovered = recover(damaged).unwrap();
+        assert_eq!(recovered.payload, original);
+        assert_eq!((recovered.redundancy, recovered.corrected_bytes, recovered.repaired_shards), (Some(25), 7, 7));
+    }
+
+    #[test]
+    fn too_much_damage_is_reported() {
+        let protected = protect(&payload(100), 50).unwrap();
+        let mut damaged = protected.clone();
+        let shards = HEADER_COPIES * HEADER_LEN;
+        for byte in &mut damaged[shards..shards + 3 * (CHECKSUM_LEN + 25)] {
+            *byte ^= 1;
+        }
+        assert!(recover(damaged).is_err());
+        assert!(recover(protected[..protected.len() - 1].to_vec()).is_err());
+        assert!(protect(b"payload", 0).is_err() && protect(b"payload", MAX_REDUNDANCY + 1).is_err());
+        assert_eq!(recover(b"P2PC plain".to_vec()).unwrap().redundancy, None);
+    }
+}
@@ -0,0 +1,88 @@
+//! HTTP/0.9 over QUIC (`hq-29`): every bidirectional stream carries one `GET /path\r\n`
+//! request and the response is the raw file contents. Files are served from a root directory
+//! and paths may not leave it.
+
+use std::fs;
+use std::path::{self, Path, PathBuf};
+use std::sync::Arc;
+
+use quinn::Connection;
+
+/// Serves the requests of one connection until the client closes it.
+pub async fn handle_connection(root: Arc<Path>, connection: Connection) -> Result<(), String> {
+    // Each stream initiated by the client constitutes a new request.
+    loop {
+        let stream = match connection.accept_bi().await {
+            Err(quinn::ConnectionError::ApplicationClosed { .. }) => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+            Ok(s) => s,
+        };
+        let root = root.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_request(root, stream).await {
+                eprintln!("HTTP request failed: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle_request(
+    root: Arc<Path>,
+    (mut send, mut recv): (quinn::SendStream, quinn::RecvStream),
+) -> Result<(), String> {
+    let req = recv
+        .read_to_end(64 * 1024)
+        .await
+        .map_err(|e| format!("failed reading request: {}", e))?;
+    let resp = process_get(&root, &req)
+        .unwrap_or_else(|e| format!("failed to process request: {}\n", e).into_bytes());
+    send.write_all(&resp)
+        .await
+        .map_err(|e| format!("failed to send response: {}", e))?;
+    send.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn process_get(root: &Path, x: &[u8]) -> Result<Vec<u8>, String> {
+    if x.len() < 4 || &x[0..4] != b"GET " {
+        return Err("missing GET".to_string());
+    }
+    if x[4..].len() < 2 || &x[x.len() - 2..] != b"\r\n" {
+        return Err("missing \\r\\n".to_string());
+    }
+    let x = &x[4..x.len() - 2];
+    let end = x.iter().position(|&c| c == b' ').unwrap_or(x.len());
+    let path = std::str::from_utf8(&x[..end]).map_err(|_| "path is malformed UTF-8".to_string())?;
+    let path = Path::new(&path);
+    let mut real_path = PathBuf::from(root);
+    let mut components = path.components();
+    match components.next() {
+        Some(path::Component::RootDir) => {}
+        _ => return Err("path must be absolute".to_string()),
+    }
+    for c in components {
+        match c {
+            path::Component::Normal(x) => real_path.push(x),
+            x => return Err(format!("illegal component in path: {:?}", x)),
+        }
+    }
+    fs::read(&real_path).map_err(|e| format!("failed reading file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_stay_inside_the_root() {
+        let root = std::env::temp_dir().join(format!("p2p-http-{}", rand::random::<u64>()));
+        fs::create_dir_all(root.join("dir")).unwrap();
+        fs::write(root.join("dir/file.txt"), b"hello").unwrap();
+
+        assert_eq!(process_get(&root, b"GET /dir/file.txt\r\n").unwrap(), b"hello");
+        assert!(process_get(&root, b"GET /../etc/passwd\r\n").is_err());
+        assert!(process_get(&root, b"GET dir/file.txt\r\n").is_err());
+        assert!(process_get(&root, b"PUT /dir/file.txt\r\n").is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
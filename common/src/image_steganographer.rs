@@ -0,0 +1,1147 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use image::{DynamicImage, GenericImageView};
+use remote_trait_object::*;
+use serde::{Deserialize, Serialize};
+use crate::access::{self, AccessControl, AccessPolicy, AccessRules};
+use crate::animation::{self, Animation};
+use crate::audio::{self, Wav};
+use crate::bundle::{self, Bundle};
+use crate::carriers::{Carrier, CarrierCapacity, CarrierLibrary, FitReport};
+use crate::certs::ClusterKey;
+use crate::container::{self, ContainerError, HiddenFile};
+use crate::crypto::{self, Encryption};
+use crate::dct::{self, DctParams};
+use crate::error::StegError;
+use crate::fec;
+use crate::preview::{self, PreviewOptions};
+use crate::probe::{self, Probe};
+use crate::provenance::{self, VerifyReport};
+use crate::quality::{self, QualityReport};
+use crate::stego::{self, EmbedKey, Mode};
+use crate::users::{Permission, Session, ANONYMOUS};
+
+
+// Method ids are assigned by declaration order, so new methods go at the end of the trait
+// and the pinned ids in the tests below have to be updated together with it. Errors are
+// returned as `StegError` so clients can decide whether a retry can help.
+#[remote_trait_object_macro::service]
+pub trait ImageSteganographer: Send + Sync {
+    /// Hides `secret` in `carrier` and returns the stego image as PNG. `file_name` is stored with
+    /// the secret and must be a plain file name; the server never writes anything to disk.
+    fn encode(&self, secret: &[u8], file_name: &str, carrier: &Carrier) -> Result<Vec<u8>, StegError>;
+    /// Returns the exact bytes of the secret hidden in a stego image produced by `encode`.
+    fn decode(&self, stego_image: &[u8]) -> Result<Vec<u8>, StegError>;
+    /// Payload capacity in every mode of each image `carrier` may resolve to.
+    fn capacity(&self, carrier: &Carrier) -> Result<Vec<CarrierCapacity>, StegError>;
+    /// Checks whether a secret of `secret_len` bytes fits into `carrier` without encoding it.
+    fn check_fit(&self, secret_len: u64, carrier: &Carrier, mode: Mode) -> Result<FitReport, StegError>;
+    /// `encode` with options; the secret is sealed first if `options.encryption` is set.
+    fn encode_with(&self, secret: &[u8], file_name: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<Vec<u8>, StegError>;
+    /// `decode` with options; encrypted secrets need the key they were sealed with.
+    fn decode_with(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<Vec<u8>, StegError>;
+    /// `check_fit` for a secret encoded with `options`, counting what they add to the payload.
+    fn check_fit_with(&self, secret_len: u64, carrier: &Carrier, options: &EncodeOptions) -> Result<FitReport, StegError>;
+    /// Reveals a secret encoded with access rules to the calling user if its policy allows it.
+    /// The returned stego image carries the updated view count and replaces the one passed in.
+    fn view(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<ViewResult, StegError>;
+    /// `encode_with`, also reporting how far the stego image is from its carrier.
+    fn encode_with_report(&self, secret: &[u8], file_name: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<EncodeReport, StegError>;
+    /// `decode_with`, returning the secret together with the name and metadata it was encoded with.
+    fn decode_file(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<HiddenFile, StegError>;
+    /// Hides `secret` in a WAV `carrier` and returns the stego audio as WAV in the carrier's
+    /// format. Access rules and [`Mode::Dct`] are for images only.
+    fn encode_audio(&self, secret: &[u8], file_name: &str, carrier: &[u8], options: &EncodeOptions) -> Result<Vec<u8>, StegError>;
+    /// Returns the file hidden in stego audio produced by `encode_audio`.
+    fn decode_audio(&self, stego_audio: &[u8], options: &DecodeOptions) -> Result<HiddenFile, StegError>;
+    /// Renders a small JPEG preview of `image` that can be published while the full image
+    /// stays hidden in a stego carrier.
+    fn preview(&self, image: &[u8], options: &PreviewOptions) -> Result<Vec<u8>, StegError>;
+    /// Checks the provenance signature of a stego image against this cluster's key. Nothing
+    /// hidden in the image is revealed. Images in [`Mode::Scattered`] read as unsigned, as
+    /// their key is not sent along.
+    fn verify(&self, stego_image: &[u8]) -> Result<VerifyReport, StegError>;
+    /// `decode_file`, also reporting what error correction had to repair.
+    fn decode_with_report(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<DecodeReport, StegError>;
+    /// Tells whether a stego image carries a payload, and in which mode and format, by reading
+    /// only the start of it. Images in [`Mode::Scattered`] are only found with their key.
+    fn probe(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<Probe, StegError>;
+    /// Hides several files together with a manifest of them, one stego image per part. A
+    /// bundle too big for the first carrier goes on into the next, and the last carrier is
+    /// used for all remaining parts. Access rules are for single files only; `options.mime`
+    /// and `options.metadata` are replaced by those of each file, and an empty MIME type is
+    /// guessed.
+    fn encode_files(&self, files: &[HiddenFile], carriers: &[Carrier], options: &EncodeOptions) -> Result<Vec<Vec<u8>>, StegError>;
+    /// Reads the files of a bundle out of its stego images, given in any order. Parts that are
+    /// not given show up in the manifest only. An image from `encode` reads as a bundle of one.
+    fn decode_files(&self, stego_images: &[Vec<u8>], options: &DecodeOptions) -> Result<Bundle, StegError>;
+    /// Hides a text message, such as a caption, in `carrier`. It is stored like a file named
+    /// [`MESSAGE_NAME`], so `decode_file` returns it too.
+    fn hide_text(&self, text: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<Vec<u8>, StegError>;
+    /// Returns a message hidden with `hide_text`, or any other hidden file that is UTF-8 text.
+    fn reveal_text(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<String, StegError>;
+    /// How long a message `hide_text` can put into the largest image `carrier` may resolve to.
+    fn text_capacity(&self, carrier: &Carrier, options: &EncodeOptions) -> Result<TextCapacity, StegError>;
+}
+impl Service for dyn ImageSteganographer {}
+
+/// Name text messages are stored under.
+pub const MESSAGE_NAME: &str = "message.txt";
+const MESSAGE_MIME: &str = "text/plain; charset=utf-8";
+
+/// Room for a text message. UTF-8 takes one to four bytes per character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextCapacity {
+    pub bytes: u64,
+    /// Characters that fit if all of them are ASCII.
+    pub ascii_chars: u64,
+    /// Characters that fit whatever they are.
+    pub chars: u64,
+}
+
+/// Per-call settings for `encode_with`. New fields must default so older clients keep working.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncodeOptions {
+    /// Seal the secret with ChaCha20-Poly1305 before hiding it. Callers sizing carriers with
+    /// `check_fit` should add [`EncodeOptions::overhead`] to the secret length.
+    #[serde(default)]
+    pub encryption: Option<Encryption>,
+    /// Restrict who may view the secret and how often; it can then only be read with `view`.
+    #[serde(default)]
+    pub access: Option<AccessRules>,
+    /// How the payload is laid out. [`Mode::Dct`] survives JPEG recompression at the node's
+    /// `compression_quality` but holds far less.
+    #[serde(default)]
+    pub mode: Mode,
+    /// MIME type stored with the secret; guessed from its bytes and name if not given.
+    #[serde(default)]
+    pub mime: Option<String>,
+    /// Free-form entries stored with the secret and returned by `decode_file`.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Key that picks the pixels in [`Mode::Scattered`]; required there and refused otherwise.
+    #[serde(default)]
+    pub embed_key: Option<EmbedKey>,
+    /// Reed-Solomon parity to add, as a percentage of the payload (1-100), so the secret
+    /// survives a few damaged pixels. 25 repairs damage to up to a fifth of the payload.
+    #[serde(default)]
+    pub redundancy: Option<u8>,
+}
+
+impl EncodeOptions {
+    /// Bytes the container and encryption add to the hidden payload, allowing for the longest
+    /// file name. Use `check_fit_with` to include the access policy, provenance signature and
+    /// error correction too.
+    pub fn overhead(&self) -> usize {
+        container::overhead(&self.metadata) + self.encryption.as_ref().map_or(0, crypto::overhead)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EncodeReport {
+    pub stego_image: Vec<u8>,
+    pub quality: QualityReport,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecodeReport {
+    pub file: HiddenFile,
+    /// Bytes of the payload that were damaged and repaired by error correction.
+    pub corrected_bytes: u64,
+    /// Shards of the payload that failed their checksum and were rebuilt from the others.
+    pub repaired_shards: u32,
+}
+
+/// Per-call settings for `decode_with`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DecodeOptions {
+    #[serde(default)]
+    pub encryption: Option<Encryption>,
+    /// Key the image was hidden with in [`Mode::Scattered`].
+    #[serde(default)]
+    pub embed_key: Option<EmbedKey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ViewResult {
+    pub file: HiddenFile,
+    /// The stego image with the view count taken down, in the mode it was encoded in.
+    pub stego_image: Vec<u8>,
+    pub remaining_views: u32,
+}
+
+// Outcome of an encode: the carrier that was picked, the payload hidden in it and the result.
+// For an animation the carrier is its first frame and the capacity that of all frames.
+struct Hidden {
+    carrier: Arc<DynamicImage>,
+    payload: Vec<u8>,
+    stego_image: Vec<u8>,
+    capacity: usize,
+}
+
+// A stego image or animation a probe found a payload in, with the mode it was found in
+enum Stego {
+    Image(DynamicImage, Mode),
+    Animation(Animation),
+}
+
+pub struct SomeImageSteganographer {
+    compression_quality: u8,  // For JPEG output (1-100)
+    max_pixel_diff: u8,      // Max RGB difference allowed per pixel
+    session: Option<Session>, // Caller the object was handed out to; None for local use
+    carriers: Arc<CarrierLibrary>,
+    access: Option<Arc<AccessControl>>, // None if the node has no cluster key
+    pool: Option<Arc<rayon::ThreadPool>>, // Pool calls embed and extract on; None for the global one
+}
+
+impl SomeImageSteganographer {
+    pub fn new(compression_quality: u8, max_pixel_diff: u8) -> Self {
+        Self {
+            compression_quality: compression_quality.clamp(1, 100),
+            max_pixel_diff: max_pixel_diff.clamp(1, 255),
+            session: None,
+            carriers: Arc::new(CarrierLibrary::default()),
+            access: None,
+            pool: None,
+        }
+    }
+
+    /// Library to pick carriers from when the client does not send its own.
+    pub fn with_carriers(mut self, carriers: Arc<CarrierLibrary>) -> Self {
+        self.carriers = carriers;
+        self
+    }
+
+    /// Enables access policies and provenance signatures with the cluster key held by `access`.
+    pub fn with_access_control(mut self, access: Arc<AccessControl>) -> Self {
+        self.access = Some(access);
+        self
+    }
+
+    /// Embeds and extracts on `pool` instead of the global rayon pool. Sessions handed the same
+    /// pool share its threads, so large images cannot take every core from the rest of the node.
+    pub fn with_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Binds the service object to the user of a connection. Every call is then checked
+    /// against that user's permissions and quota.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = Some(session);
+        self
+    }
+
+    fn authorize(&self, permission: Permission, bytes: usize) -> Result<(), StegError> {
+        match &self.session {
+            Some(session) => session.authorize(permission, bytes).map_err(StegError::Unauthorized),
+            None => Ok(()),
+        }
+    }
+
+    // Dry runs are not charged against the quota.
+    fn permit(&self, permission: Permission) -> Result<(), StegError> {
+        match &self.session {
+            Some(session) => session.permit(permission).map_err(StegError::Unauthorized),
+            None => Ok(()),
+        }
+    }
+
+    // Runs `job` on the pool the object was given, on the global rayon pool if none
+    fn parallel<T: Send>(&self, job: impl FnOnce() -> T + Send) -> T {
+        match &self.pool {
+            Some(pool) => pool.install(job),
+            None => job(),
+        }
+    }
+
+    fn dct_params(&self) -> DctParams {
+        DctParams { quality: self.compression_quality, max_pixel_diff: self.max_pixel_diff }
+    }
+
+    fn user(&self) -> &str {
+        self.session.as_ref().map_or(ANONYMOUS, Session::user)
+    }
+
+    fn access_control(&self) -> Result<&AccessControl, StegError> {
+        self.access.as_deref().ok_or_else(|| {
+            StegError::Unsupported("this node has no cluster key and cannot handle access policies".to_string())
+        })
+    }
+
+    // Nodes with the cluster key sign everything they embed
+    fn signer(&self) -> Option<&ClusterKey> {
+        self.access.as_deref().map(AccessControl::key)
+    }
+
+    fn policy(&self, options: &EncodeOptions) -> Option<AccessPolicy> {
+        options.access.as_ref().map(|rules| AccessPolicy::new(self.user(), rules))
+    }
+
+    fn hide(&self, secret: &[u8], file_name: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<Hidden, StegError> {
+        let uploaded = match carrier {
+            Carrier::Image(bytes) => bytes.len(),
+            _ => 0,
+        };
+        self.authorize(Permission::Encode, secret.len() + uploaded)?;
+        Self::check_animated(carrier, options)?;
+        let payload = self.wrap(secret, file_name, options)?;
+        self.place(carrier, payload, options)
+    }
+
+    // Embeds a wrapped payload in the image `carrier` resolves to. Animated GIFs stay GIFs,
+    // with the payload spread over their frames.
+    fn place(&self, carrier: &Carrier, payload: Vec<u8>, options: &EncodeOptions) -> Result<Hidden, StegError> {
+        if let Some(bytes) = Self::animated(carrier) {
+            let animation = Animation::read(bytes).map_err(|e| StegError::InvalidImage(format!("carrier: {}", e)))?;
+            let capacity = animation.capacity();
+            if payload.len() > capacity {
+                return Err(StegError::PayloadTooLarge { payload: payload.len() as u64, capacity: capacity as u64 });
+            }
+            let stego = animation::embed(&animation, &payload).map_err(StegError::EmbedFailed)?;
+            let stego_image = stego.write().map_err(StegError::Internal)?;
+            return Ok(Hidden { carrier: Arc::new(Self::load(bytes)?), payload, stego_image, capacity });
+        }
+        let carrier = self.carriers.select(carrier, payload.len(), options.mode)?;
+        let stego_image = self.embed(&carrier, &payload, options.mode, options.embed_key.as_ref())?;
+        let (width, height) = carrier.dimensions();
+        Ok(Hidden { capacity: options.mode.capacity(width, height), carrier, payload, stego_image })
+    }
+
+    fn animated(carrier: &Carrier) -> Option<&[u8]> {
+        match carrier {
+            Carrier::Image(bytes) if animation::is_gif(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    // Views re-embed the payload in a still image, so animations only take plain LSB payloads
+    fn check_animated(carrier: &Carrier, options: &EncodeOptions) -> Result<(), StegError> {
+        if Self::animated(carrier).is_some() && (options.access.is_some() || options.mode != Mode::Lsb || options.embed_key.is_some()) {
+            return Err(StegError::InvalidRequest("animated carriers only support plain LSB embedding without access rules".to_string()));
+        }
+        Ok(())
+    }
+
+    // Packs the secret into its container and applies encryption and access rules, giving
+    // the payload to embed in any kind of carrier
+    fn wrap(&self, secret: &[u8], file_name: &str, options: &EncodeOptions) -> Result<Vec<u8>, StegError> {
+        let file = HiddenFile {
+            name: file_name.to_string(),
+            mime: options.mime.clone().unwrap_or_else(|| container::sniff_mime(file_name, secret)),
+            metadata: options.metadata.clone(),
+            content: secret.to_vec(),
+        };
+        self.enclose(container::pack(&file).map_err(StegError::InvalidRequest)?, options)
+    }
+
+    // Wraps a container or bundle part in the layers `options` ask for
+    fn enclose(&self, mut payload: Vec<u8>, options: &EncodeOptions) -> Result<Vec<u8>, StegError> {
+        if let Some(encryption) = &options.encryption {
+            payload = crypto::seal(&payload, encryption)?;
+        }
+        if let Some(policy) = self.policy(options) {
+            payload = self.access_control()?.protect(&policy, &payload)?;
+        }
+        if let Some(key) = self.signer() {
+            payload = provenance::sign(key, &payload, access::now());
+        }
+        if let Some(redundancy) = options.redundancy {
+            payload = fec::protect(&payload, redundancy).map_err(StegError::InvalidRequest)?;
+        }
+        Ok(payload)
+    }
+
+    // Strips error correction off an extracted payload, repairing what it can
+    fn repair(payload: Vec<u8>) -> Result<fec::Recovered, StegError> {
+        fec::recover(payload).map_err(StegError::CorruptPayload)
+    }
+
+    // Size of the payload embedded for `inner` bytes of container or bundle part
+    fn wrapped_len(&self, inner: usize, options: &EncodeOptions) -> Result<usize, StegError> {
+        let mut payload = inner + options.encryption.as_ref().map_or(0, crypto::overhead);
+        if let Some(policy) = self.policy(options) {
+            payload += self.access_control()?.overhead(&policy);
+        }
+        if self.signer().is_some() {
+            payload += provenance::OVERHEAD;
+        }
+        if let Some(redundancy) = options.redundancy {
+            fec::check_redundancy(redundancy).map_err(StegError::InvalidRequest)?;
+            payload = fec::encoded_len(payload, redundancy);
+        }
+        Ok(payload)
+    }
+
+    // Most bytes of container or bundle part whose payload still fits into `capacity` bytes
+    fn room(&self, capacity: usize, options: &EncodeOptions) -> Result<usize, StegError> {
+        let (mut fits, mut too_big) = (0, capacity + 1);
+        while too_big - fits > 1 {
+            let inner = (fits + too_big) / 2;
+            if self.wrapped_len(inner, options)? <= capacity {
+                fits = inner;
+            } else {
+                too_big = inner;
+            }
+        }
+        Ok(fits)
+    }
+
+    // Opens a payload that is not under an access policy
+    fn open(payload: Vec<u8>, options: &DecodeOptions) -> Result<HiddenFile, StegError> {
+        Self::unseal(Self::unprotected(payload)?, options)
+    }
+
+    // Strips the signature off a payload, refusing those under an access policy
+    fn unprotected(payload: Vec<u8>) -> Result<Vec<u8>, StegError> {
+        let payload = provenance::inner(&payload).to_vec();
+        if access::is_protected(&payload) {
+            return Err(StegError::InvalidRequest("image is protected by an access policy, use view".to_string()));
+        }
+        Ok(payload)
+    }
+
+    // Peels the encryption off a payload and opens the container inside
+    fn unseal(payload: Vec<u8>, options: &DecodeOptions) -> Result<HiddenFile, StegError> {
+        let payload = Self::decrypt(payload, options)?;
+        if bundle::is_bundle(&payload) {
+            return Err(StegError::InvalidRequest("image holds several files, use decode_files".to_string()));
+        }
+        Ok(container::unpack(&payload)?)
+    }
+
+    fn decrypt(payload: Vec<u8>, options: &DecodeOptions) -> Result<Vec<u8>, StegError> {
+        match &options.encryption {
+            _ if !crypto::is_sealed(&payload) => Ok(payload),
+            Some(encryption) => Ok(crypto::open(&payload, encryption)?),
+            None => Err(StegError::KeyRequired),
+        }
+    }
+
+    fn load(stego_image: &[u8]) -> Result<DynamicImage, StegError> {
+        image::load_from_memory(stego_image).map_err(|e| StegError::InvalidImage(e.to_string()))
+    }
+
+    // The payload is read in whatever mode it was written in
+    fn embed(&self, carrier: &DynamicImage, payload: &[u8], mode: Mode, key: Option<&EmbedKey>) -> Result<Vec<u8>, StegError> {
+        let stego_image = match (mode, key) {
+            (Mode::Scattered, Some(key)) => self.parallel(|| stego::hide_scattered(carrier, payload, key)),
+            (Mode::Scattered, None) => {
+                return Err(StegError::InvalidRequest("scattered mode needs an embedding key".to_string()))
+            }
+            (_, Some(_)) => {
+                return Err(StegError::InvalidRequest("an embedding key is only used in scattered mode".to_string()))
+            }
+            (mode, None) => self.parallel(|| stego::hide_with(carrier, payload, mode, &self.dct_params())),
+        };
+        stego_image.map_err(StegError::EmbedFailed)
+    }
+
+    // Reads the payload in the mode a probe found it in
+    fn extract_in(&self, stego: &DynamicImage, mode: Mode, key: Option<&EmbedKey>) -> Result<Vec<u8>, StegError> {
+        let payload = self.parallel(|| match (mode, key) {
+            (Mode::Scattered, Some(key)) => stego::extract_scattered(stego, key),
+            (Mode::Dct, _) => dct::extract(stego, &self.dct_params()),
+            _ => stego::extract(stego),
+        });
+        Self::layered(payload.map_err(|_| StegError::NoPayload)?)
+    }
+
+    // Loads a stego image or animation, turning it away if a decode would fail on it anyway
+    fn examine(&self, stego_image: &[u8], key: Option<&EmbedKey>) -> Result<Stego, StegError> {
+        if animation::is_gif(stego_image) {
+            let stego = Animation::read(stego_image).map_err(StegError::InvalidImage)?;
+            Self::admit(&probe::probe_animation(&stego))?;
+            return Ok(Stego::Animation(stego));
+        }
+        let stego = Self::load(stego_image)?;
+        let mode = Self::admit(&probe::probe_image(&stego, &self.dct_params(), key))?;
+        Ok(Stego::Image(stego, mode))
+    }
+
+    fn read(&self, stego: &Stego, key: Option<&EmbedKey>) -> Result<Vec<u8>, StegError> {
+        match stego {
+            Stego::Image(image, mode) => self.extract_in(image, *mode, key),
+            Stego::Animation(animation) => Self::layered(animation::extract(animation).map_err(|_| StegError::NoPayload)?),
+        }
+    }
+
+    // Turns away images a decode would fail on anyway, before the full payload is read
+    fn admit(probe: &Probe) -> Result<Mode, StegError> {
+        match (probe.mode, probe.version) {
+            (None, _) => Err(StegError::NoPayload),
+            (Some(_), Some(version)) if !probe.supported() => Err(StegError::UnsupportedVersion(version)),
+            (Some(mode), _) => Ok(mode),
+        }
+    }
+
+    fn extract(&self, stego: &DynamicImage, key: Option<&EmbedKey>) -> Result<(Mode, Vec<u8>), StegError> {
+        // Without its key a scattered image reads as noise, so the keyed reading goes first
+        if let Some(key) = key {
+            if let Ok(payload) = Self::layered(stego::extract_scattered(stego, key).unwrap_or_default()) {
+                return Ok((Mode::Scattered, payload));
+            }
+        }
+        let extracted = self.parallel(|| stego::extract_any(stego, &self.dct_params()));
+        let (mode, payload) = extracted.map_err(|_| StegError::NoPayload)?;
+        Ok((mode, Self::layered(payload)?))
+    }
+
+    // Whatever the low bits of a plain carrier read as, they will not start like one of our layers
+    fn layered(payload: Vec<u8>) -> Result<Vec<u8>, StegError> {
+        let ours = [
+            container::is_container, bundle::is_bundle, crypto::is_sealed, access::is_protected, provenance::is_signed, fec::is_protected,
+        ];
+        if !ours.iter().any(|is| is(&payload)) {
+            return Err(StegError::NoPayload);
+        }
+        Ok(payload)
+    }
+}
+
+
+
+impl ImageSteganographer for SomeImageSteganographer {
+
+
+    fn encode(&self, secret: &[u8], file_name: &str, carrier: &Carrier) -> Result<Vec<u8>, StegError> {
+
+        self.encode_with(secret, file_name, carrier, &EncodeOptions::default())
+    }
+
+
+    fn decode(&self, stego_image: &[u8]) -> Result<Vec<u8>, StegError> {
+
+        self.decode_with(stego_image, &DecodeOptions::default())
+    }
+
+
+    fn capacity(&self, carrier: &Carrier) -> Result<Vec<CarrierCapacity>, StegError> {
+
+        self.permit(Permission::Encode)?;
+
+        self.carriers.capacities(carrier)
+    }
+
+
+    fn check_fit(&self, secret_len: u64, carrier: &Carrier, mode: Mode) -> Result<FitReport, StegError> {
+
+        self.permit(Permission::Encode)?;
+
+        self.carriers.check_fit(carrier, secret_len as usize, mode)
+    }
+
+
+    fn encode_with(&self, secret: &[u8], file_name: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<Vec<u8>, StegError> {
+
+        self.hide(secret, file_name, carrier, options).map(|hidden| hidden.stego_image)
+    }
+
+
+    fn decode_with(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<Vec<u8>, StegError> {
+
+        self.decode_file(stego_image, options).map(|file| file.content)
+    }
+
+
+    fn check_fit_with(&self, secret_len: u64, carrier: &Carrier, options: &EncodeOptions) -> Result<FitReport, StegError> {
+
+        self.permit(Permission::Encode)?;
+
+        let payload = self.wrapped_len(secret_len as usize + container::overhead(&options.metadata), options)?;
+        self.carriers.check_fit(carrier, payload, options.mode)
+    }
+
+
+    fn view(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<ViewResult, StegError> {
+
+        self.authorize(Permission::Decode, stego_image.len())?;
+
+        let stego = Self::load(stego_image)?;
+        let (mode, payload) = self.extract(&stego, options.embed_key.as_ref())?;
+        let recovered = Self::repair(payload)?;
+        let access = self.access_control()?;
+        let view = access.view(provenance::inner(&recovered.payload), self.user(), access::now())?;
+        let mut payload = provenance::sign(access.key(), &view.payload, access::now());
+        if let Some(redundancy) = recovered.redundancy {
+            payload = fec::protect(&payload, redundancy).map_err(StegError::Internal)?;
+        }
+        let key = options.embed_key.as_ref().filter(|_| mode == Mode::Scattered);
+        let stego_image = self.embed(&stego, &payload, mode, key)?;
+        let file = Self::unseal(view.secret, options)?;
+        // Only a view that got all the way through is used up
+        access.commit(&view.policy, self.user())?;
+        Ok(ViewResult { file, stego_image, remaining_views: view.policy.remaining_views })
+    }
+
+
+    fn encode_with_report(&self, secret: &[u8], file_name: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<EncodeReport, StegError> {
+
+        let hidden = self.hide(secret, file_name, carrier, options)?;
+        // Measured on the image as delivered, after any lossy compression; on the first frame of
+        // an animation
+        let stego = image::load_from_memory(&hidden.stego_image).map_err(|e| StegError::Internal(e.to_string()))?;
+        let quality = quality::measure(&hidden.carrier, &stego, hidden.payload.len(), hidden.capacity).map_err(StegError::Internal)?;
+        Ok(EncodeReport { stego_image: hidden.stego_image, quality })
+    }
+
+
+    fn decode_file(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<HiddenFile, StegError> {
+
+        self.decode_with_report(stego_image, options).map(|report| report.file)
+    }
+
+
+    fn encode_audio(&self, secret: &[u8], file_name: &str, carrier: &[u8], options: &EncodeOptions) -> Result<Vec<u8>, StegError> {
+
+        self.authorize(Permission::Encode, secret.len() + carrier.len())?;
+
+        // Views re-embed the payload, which only works for images
+        if options.access.is_some() || options.mode != Mode::Lsb || options.embed_key.is_some() {
+            return Err(StegError::InvalidRequest("audio carriers only support plain LSB embedding without access rules".to_string()));
+        }
+        let wav = Wav::read(carrier).map_err(StegError::InvalidAudio)?;
+        let payload = self.wrap(secret, file_name, options)?;
+        if payload.len() > wav.capacity() {
+            return Err(StegError::PayloadTooLarge { payload: payload.len() as u64, capacity: wav.capacity() as u64 });
+        }
+        let stego = audio::embed(&wav, &payload).map_err(StegError::EmbedFailed)?;
+        stego.write().map_err(StegError::Internal)
+    }
+
+
+    fn decode_audio(&self, stego_audio: &[u8], options: &DecodeOptions) -> Result<HiddenFile, StegError> {
+
+        self.authorize(Permission::Decode, stego_audio.len())?;
+
+        let wav = Wav::read(stego_audio).map_err(StegError::InvalidAudio)?;
+        let payload = audio::extract(&wav).map_err(|_| StegError::NoPayload)?;
+        Self::open(Self::repair(Self::layered(payload)?)?.payload, options)
+    }
+
+
+    fn preview(&self, image: &[u8], options: &PreviewOptions) -> Result<Vec<u8>, StegError> {
+
+        self.authorize(Permission::Encode, image.len() + options.watermark.as_ref().map_or(0, Vec::len))?;
+
+        options.validate().map_err(StegError::InvalidRequest)?;
+        let image = Self::load(image)?;
+        let watermark = options.watermark.as_deref().map(Self::load).transpose()?;
+        preview::render(&image, watermark.as_ref(), options).map_err(StegError::Internal)
+    }
+
+
+    fn verify(&self, stego_image: &[u8]) -> Result<VerifyReport, StegError> {
+
+        self.authorize(Permission::Decode, stego_image.len())?;
+
+        let key = self.signer().ok_or_else(|| StegError::Unsupported("this node has no cluster key to verify with".to_string()))?;
+        // An image without a payload is reported as unsigned
+        let payload = if animation::is_gif(stego_image) {
+            animation::extract(&Animation::read(stego_image).map_err(StegError::InvalidImage)?).unwrap_or_default()
+        } else {
+            let stego = Self::load(stego_image)?;
+            let extracted = self.parallel(|| stego::extract_any(&stego, &self.dct_params()));
+            extracted.map(|(_, payload)| payload).unwrap_or_default()
+        };
+        let payload = Self::repair(payload)?.payload;
+        Ok(provenance::verify(&payload, &key.verifying_key()))
+    }
+
+
+    fn decode_with_report(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<DecodeReport, StegError> {
+
+        // Images that carry nothing readable are refused before the quota is charged
+        self.permit(Permission::Decode)?;
+        let key = options.embed_key.as_ref();
+        let stego = self.examine(stego_image, key)?;
+        self.authorize(Permission::Decode, stego_image.len())?;
+
+        let payload = self.read(&stego, key)?;
+        let recovered = Self::repair(payload)?;
+        Ok(DecodeReport {
+            file: Self::open(recovered.payload, options)?,
+            corrected_bytes: recovered.corrected_bytes as u64,
+            repaired_shards: recovered.repaired_shards as u32,
+        })
+    }
+
+
+    fn probe(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<Probe, StegError> {
+
+        self.permit(Permission::Decode)?;
+
+        probe::probe(stego_image, &self.dct_params(), options.embed_key.as_ref()).map_err(StegError::InvalidImage)
+    }
+
+
+    fn encode_files(&self, files: &[HiddenFile], carriers: &[Carrier], options: &EncodeOptions) -> Result<Vec<Vec<u8>>, StegError> {
+
+        let uploaded: usize = carriers.iter().map(|c| if let Carrier::Image(bytes) = c { bytes.len() } else { 0 }).sum();
+        self.authorize(Permission::Encode, files.iter().map(|f| f.content.len()).sum::<usize>() + uploaded)?;
+
+        if options.access.is_some() {
+            return Err(StegError::InvalidRequest("access rules can only be set on single files".to_string()));
+        }
+        if carriers.is_empty() {
+            return Err(StegError::InvalidRequest("at least one carrier is needed".to_string()));
+        }
+        for carrier in carriers {
+            Self::check_animated(carrier, options)?;
+        }
+        let files: Vec<HiddenFile> = files
+            .iter()
+            .map(|file| HiddenFile {
+                mime: if file.mime.is_empty() { container::sniff_mime(&file.name, &file.content) } else { file.mime.clone() },
+                ..file.clone()
+            })
+            .collect();
+        // The most a part may hold so that it still fits the largest image its carrier may be
+        let capacities = carriers
+            .iter()
+            .map(|carrier| {
+                let capacities = self.carriers.capacities(carrier)?;
+                self.room(capacities.iter().map(|c| c.bytes(options.mode)).max().unwrap_or_default(), options)
+            })
+            .collect::<Result<Vec<usize>, StegError>>()?;
+        let parts = bundle::pack(&files, |part| capacities[part.min(capacities.len() - 1)])?;
+
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(part, inner)| {
+                let payload = self.enclose(inner, options)?;
+                let hidden = self.place(&carriers[part.min(carriers.len() - 1)], payload, options)?;
+                Ok(hidden.stego_image)
+            })
+            .collect()
+    }
+
+
+    fn decode_files(&self, stego_images: &[Vec<u8>], options: &DecodeOptions) -> Result<Bundle, StegError> {
+
+        self.authorize(Permission::Decode, stego_images.iter().map(Vec::len).sum())?;
+
+        let key = options.embed_key.as_ref();
+        let mut parts = Vec::new();
+        for stego_image in stego_images {
+            let stego = self.examine(stego_image, key)?;
+            let payload = Self::repair(self.read(&stego, key)?)?.payload;
+            let payload = Self::decrypt(Self::unprotected(payload)?, options)?;
+            if !bundle::is_bundle(&payload) && stego_images.len() == 1 {
+                return Ok(Bundle::single(container::unpack(&payload)?));
+            }
+            parts.push(bundle::unpack(&payload).map_err(|e| match e {
+                ContainerError::NoPayload => StegError::InvalidRequest("an image holding a single file was given with others".to_string()),
+                e => e.into(),
+            })?);
+        }
+        bundle::join(parts)
+    }
+
+
+    fn hide_text(&self, text: &str, carrier: &Carrier, options: &EncodeOptions) -> Result<Vec<u8>, StegError> {
+
+        let options = EncodeOptions { mime: Some(MESSAGE_MIME.to_string()), ..options.clone() };
+        self.encode_with(text.as_bytes(), MESSAGE_NAME, carrier, &options)
+    }
+
+
+    fn reveal_text(&self, stego_image: &[u8], options: &DecodeOptions) -> Result<String, StegError> {
+
+        let file = self.decode_file(stego_image, options)?;
+        String::from_utf8(file.content)
+            .map_err(|_| StegError::InvalidRequest(format!("{} is not UTF-8 text, use decode_file", file.name)))
+    }
+
+
+    fn text_capacity(&self, carrier: &Carrier, options: &EncodeOptions) -> Result<TextCapacity, StegError> {
+
+        self.permit(Permission::Encode)?;
+
+        let capacities = self.carriers.capacities(carrier)?;
+        let largest = capacities.iter().map(|c| c.bytes(options.mode)).max().unwrap_or_default();
+        let empty = HiddenFile {
+            name: MESSAGE_NAME.to_string(),
+            mime: MESSAGE_MIME.to_string(),
+            metadata: options.metadata.clone(),
+            content: Vec::new(),
+        };
+        let container = container::pack(&empty).map_err(StegError::InvalidRequest)?.len();
+        let bytes = self.room(largest, options)?.saturating_sub(container) as u64;
+        Ok(TextCapacity { bytes, ascii_chars: bytes, chars: bytes / 4 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remote_trait_object::macro_env::{ID_ORDERING, MID_REG};
+
+    // These pin the wire contract of the service. If one of them fails, a client and a
+    // server built from different revisions can no longer talk to each other.
+
+    #[test]
+    fn method_ids_are_pinned() {
+        assert_eq!(ID_METHOD_ImageSteganographer_encode.load(ID_ORDERING), 70);
+        assert_eq!(ID_METHOD_ImageSteganographer_decode.load(ID_ORDERING), 71);
+        assert_eq!(ID_METHOD_ImageSteganographer_capacity.load(ID_ORDERING), 72);
+        assert_eq!(ID_METHOD_ImageSteganographer_check_fit.load(ID_ORDERING), 73);
+        assert_eq!(ID_METHOD_ImageSteganographer_encode_with.load(ID_ORDERING), 74);
+        assert_eq!(ID_METHOD_ImageSteganographer_decode_with.load(ID_ORDERING), 75);
+        assert_eq!(ID_METHOD_ImageSteganographer_check_fit_with.load(ID_ORDERING), 76);
+        assert_eq!(ID_METHOD_ImageSteganographer_view.load(ID_ORDERING), 77);
+        assert_eq!(ID_METHOD_ImageSteganographer_encode_with_report.load(ID_ORDERING), 78);
+        assert_eq!(ID_METHOD_ImageSteganographer_decode_file.load(ID_ORDERING), 79);
+        assert_eq!(ID_METHOD_ImageSteganographer_encode_audio.load(ID_ORDERING), 80);
+        assert_eq!(ID_METHOD_ImageSteganographer_decode_audio.load(ID_ORDERING), 81);
+        assert_eq!(ID_METHOD_ImageSteganographer_preview.load(ID_ORDERING), 82);
+        assert_eq!(ID_METHOD_ImageSteganographer_verify.load(ID_ORDERING), 83);
+        assert_eq!(ID_METHOD_ImageSteganographer_decode_with_report.load(ID_ORDERING), 84);
+        assert_eq!(ID_METHOD_ImageSteganographer_probe.load(ID_ORDERING), 85);
+        assert_eq!(ID_METHOD_ImageSteganographer_encode_files.load(ID_ORDERING), 86);
+        assert_eq!(ID_METHOD_ImageSteganographer_decode_files.load(ID_ORDERING), 87);
+        assert_eq!(ID_METHOD_ImageSteganographer_hide_text.load(ID_ORDERING), 88);
+        assert_eq!(ID_METHOD_ImageSteganographer_reveal_text.load(ID_ORDERING), 89);
+        assert_eq!(ID_METHOD_ImageSteganographer_text_capacity.load(ID_ORDERING), 90);
+    }
+
+    #[test]
+    fn method_set_is_pinned() {
+        let mut methods: Vec<&str> = MID_REG
+            .iter()
+            .filter(|(trait_name, _, _)| *trait_name == "ImageSteganographer")
+            .map(|(_, method_name, _)| *method_name)
+            .collect();
+        methods.sort_unstable();
+        assert_eq!(methods, vec![
+            "capacity", "check_fit", "check_fit_with", "decode", "decode_audio", "decode_file", "decode_files",
+            "decode_with", "decode_with_report", "encode", "encode_audio", "encode_files", "encode_with",
+            "encode_with_report", "hide_text", "preview", "probe", "reveal_text", "text_capacity", "verify", "view",
+        ]);
+    }
+
+    fn carrier() -> Carrier {
+        Carrier::Image(stego::hide(&image::DynamicImage::new_rgba8(64, 64), &[]).unwrap())
+    }
+
+    #[test]
+    fn encrypted_secrets_need_the_right_key() {
+        let carrier = carrier();
+        let steg = SomeImageSteganographer::new(75, 10);
+        let options = EncodeOptions { encryption: Some(Encryption::Key([3; 32])), ..Default::default() };
+        let stego_image = steg.encode_with(b"secret", "secret.png", &carrier, &options).unwrap();
+
+        let right = DecodeOptions { encryption: Some(Encryption::Key([3; 32])), ..Default::default() };
+        let wrong = DecodeOptions { encryption: Some(Encryption::Key([4; 32])), ..Default::default() };
+        assert_eq!(steg.decode_with(&stego_image, &right).unwrap(), b"secret");
+        assert_eq!(steg.decode_with(&stego_image, &wrong), Err(StegError::WrongKey));
+        assert_eq!(steg.decode(&stego_image), Err(StegError::KeyRequired));
+    }
+
+    #[test]
+    fn views_are_limited_by_the_embedded_policy() {
+        let users = Arc::new(crate::users::UserDirectory::parse(r#"
+            [users.alice]
+            permissions = ["encode", "decode"]
+            [users.bob]
+            permissions = ["decode"]
+        "#).unwrap());
+        let access = Arc::new(AccessControl::new(crate::certs::ClusterKey::generate()));
+        let as_user = |user: &str| SomeImageSteganographer::new(75, 10)
+            .with_session(users.session(Some(user.to_string())).unwrap())
+            .with_access_control(access.clone());
+
+        let rules = AccessRules { viewers: vec!["bob".to_string()], views: 1, expires_at: None };
+        let options = EncodeOptions { access: Some(rules), ..Default::default() };
+        let report = as_user("alice").check_fit_with(6, &carrier(), &options).unwrap();
+        let stego_image = as_user("alice").encode_with(b"secret", "secret.png", &carrier(), &options).unwrap();
+        assert!(stego::reveal(&stego_image).unwrap().len() <= report.payload);
+
+        let bob = as_user("bob");
+        assert!(matches!(bob.decode(&stego_image), Err(StegError::InvalidRequest(_))));
+        let viewed = bob.view(&stego_image, &DecodeOptions::default()).unwrap();
+        assert_eq!((viewed.file.content.as_slice(), viewed.remaining_views), (&b"secret"[..], 0));
+        assert!(matches!(bob.view(&viewed.stego_image, &DecodeOptions::default()), Err(StegError::AccessDenied(_))));
+        assert!(matches!(
+            SomeImageSteganographer::new(75, 10).view(&stego_image, &DecodeOptions::default()),
+            Err(StegError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn failed_views_are_not_counted() {
+        let users = Arc::new(crate::users::UserDirectory::parse(r#"
+            [users.alice]
+            permissions = ["encode", "decode"]
+            [users.bob]
+            permissions = ["decode"]
+        "#).unwrap());
+        let access = Arc::new(AccessControl::new(crate::certs::ClusterKey::generate()));
+        let as_user = |user: &str| SomeImageSteganographer::new(75, 10)
+            .with_session(users.session(Some(user.to_string())).unwrap())
+            .with_access_control(access.clone());
+
+        let rules = AccessRules { viewers: vec!["bob".to_string()], views: 2, expires_at: None };
+        let options = EncodeOptions { access: Some(rules), encryption: Some(Encryption::Key([6; 32])), ..Default::default() };
+        let stego_image = as_user("alice").encode_with(b"secret", "secret.png", &carrier(), &options).unwrap();
+
+        let bob = as_user("bob");
+        assert_eq!(bob.view(&stego_image, &DecodeOptions::default()).err(), Some(StegError::KeyRequired));
+        let right = DecodeOptions { encryption: Some(Encryption::Key([6; 32])), ..Default::default() };
+        let viewed = bob.view(&stego_image, &right).unwrap();
+        assert_eq!((viewed.file.content.as_slice(), viewed.remaining_views), (&b"secret"[..], 1));
+    }
+
+    #[test]
+    fn the_mode_is_chosen_per_call() {
+        let grey = image::RgbImage::from_pixel(160, 160, image::Rgb([128, 128, 128]));
+        let carrier = Carrier::Image(stego::hide(&image::DynamicImage::ImageRgb8(grey), &[]).unwrap());
+        let steg = SomeImageSteganographer::new(75, 10);
+        let dct = EncodeOptions { mode: Mode::Dct, ..Default::default() };
+        assert!(!steg.check_fit_with(200, &carrier, &dct).unwrap().fits);
+        assert!(steg.check_fit_with(200, &carrier, &EncodeOptions::default()).unwrap().fits);
+
+        let encoded = steg.encode_with_report(b"survives jpeg", "secret.txt", &carrier, &dct).unwrap();
+        let stego_image = encoded.stego_image;
+        assert_eq!(image::guess_format(&stego_image).unwrap(), image::ImageFormat::JPEG);
+        assert!(encoded.quality.psnr > 35.0 && encoded.quality.max_delta <= 10);
+        assert_eq!(encoded.quality.capacity, Mode::Dct.capacity(160, 160));
+        assert_eq!(steg.decode(&stego_image).unwrap(), b"survives jpeg");
+    }
+
+    #[test]
+    fn files_come_back_with_their_name() {
+        let steg = SomeImageSteganographer::new(75, 10);
+        let options = EncodeOptions {
+            metadata: [("album".to_string(), "summer".to_string())].into_iter().collect(),
+            ..Default::default()
+        };
+        let stego_image = steg.encode_with(b"\x89PNG\r\n\x1a\n...", "photo.png", &carrier(), &options).unwrap();
+        let file = steg.decode_file(&stego_image, &DecodeOptions::default()).unwrap();
+        assert_eq!((file.name.as_str(), file.mime.as_str()), ("photo.png", "image/png"));
+        assert_eq!(file.content, b"\x89PNG\r\n\x1a\n...");
+        assert_eq!(file.metadata, options.metadata);
+
+        let plain = stego::hide(&image::DynamicImage::new_rgba8(64, 64), b"no container here").unwrap();
+        assert_eq!(steg.decode(&plain), Err(StegError::NoPayload));
+        assert!(matches!(steg.decode(b"not an image"), Err(StegError::InvalidImage(_))));
+    }
+
+    #[test]
+    fn file_names_cannot_be_paths() {
+        let steg = SomeImageSteganographer::new(75, 10);
+        for name in ["../../root/.ssh/authorized_keys", "/tmp/evil", "..\\..\\boot.ini", ".."] {
+            assert!(matches!(steg.encode(b"secret", name, &carrier()), Err(StegError::InvalidRequest(_))), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn documents_hide_in_voice_memos() {
+        let spec = hound::WavSpec { channels: 1, sample_rate: 8000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let memo = Wav { spec, samples: (0..20_000).map(|i| (i % 200) * 50 - 5000).collect() }.write().unwrap();
+        let steg = SomeImageSteganographer::new(75, 10);
+        let options = EncodeOptions { encryption: Some(Encryption::Key([7; 32])), ..Default::default() };
+        let stego_audio = steg.encode_audio(b"minutes of the meeting", "minutes.txt", &memo, &options).unwrap();
+        assert_eq!(Wav::read(&stego_audio).unwrap().spec, spec);
+
+        let decode = DecodeOptions { encryption: options.encryption.clone(), ..Default::default() };
+        let file = steg.decode_audio(&stego_audio, &decode).unwrap();
+        assert_eq!((file.name.as_str(), file.content.as_slice()), ("minutes.txt", &b"minutes of the meeting"[..]));
+        assert_eq!(steg.decode_audio(&memo, &decode), Err(StegError::NoPayload));
+        assert!(matches!(steg.decode_audio(b"not audio", &decode), Err(StegError::InvalidAudio(_))));
+        assert!(matches!(
+            steg.encode_audio(&[0; 3000], "big.bin", &memo, &EncodeOptions::default()),
+            Err(StegError::PayloadTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn previews_are_rendered_from_uploaded_images() {
+        let Carrier::Image(image) = carrier() else { unreachable!() };
+        let steg = SomeImageSteganographer::new(75, 10);
+        let options = PreviewOptions { max_size: 16, ..Default::default() };
+        let preview = image::load_from_memory(&steg.preview(&image, &options).unwrap()).unwrap();
+        assert_eq!(preview.dimensions(), (16, 16));
+
+        let bad_watermark = PreviewOptions { watermark: Some(b"not an image".to_vec()), ..options };
+        assert!(matches!(steg.preview(&image, &bad_watermark), Err(StegError::InvalidImage(_))));
+        let too_big = PreviewOptions { max_size: 5000, ..Default::default() };
+        assert!(matches!(steg.preview(&image, &too_big), Err(StegError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn stego_images_carry_the_cluster_signature() {
+        let access = Arc::new(AccessControl::new(ClusterKey::generate()));
+        let steg = SomeImageSteganographer::new(75, 10).with_access_control(access.clone());
+        let stego_image = steg.encode(b"secret", "secret.txt", &carrier()).unwrap();
+        let report = steg.verify(&stego_image).unwrap();
+        assert_eq!(report.integrity, provenance::Integrity::Intact);
+        assert_eq!(report.signer, Some(provenance::fingerprint(&access.key().verifying_key())));
+        assert_eq!(steg.decode(&stego_image).unwrap(), b"secret");
+
+        let mut payload = stego::reveal(&stego_image).unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let tampered = stego::hide(&image::load_from_memory(&stego_image).unwrap(), &payload).unwrap();
+        assert_eq!(steg.verify(&tampered).unwrap().integrity, provenance::Integrity::Tampered);
+        let Carrier::Image(plain) = carrier() else { unreachable!() };
+        assert_eq!(steg.verify(&plain).unwrap().integrity, provenance::Integrity::Unsigned);
+        assert!(matches!(SomeImageSteganographer::new(75, 10).verify(&stego_image), Err(StegError::Unsupported(_))));
+    }
+
+    #[test]
+    fn scattered_images_need_their_key() {
+        let steg = SomeImageSteganographer::new(75, 10);
+        let key = Some(EmbedKey("pixel shuffle".to_string()));
+        let options = EncodeOptions { mode: Mode::Scattered, embed_key: key.clone(), ..Default::default() };
+        let stego_image = steg.encode_with(b"secret", "secret.txt", &carrier(), &options).unwrap();
+
+        let with_key = DecodeOptions { embed_key: key.clone(), ..Default::default() };
+        assert_eq!(steg.decode_with(&stego_image, &with_key).unwrap(), b"secret");
+        assert_eq!(steg.decode(&stego_image), Err(StegError::NoPayload));
+
+        let keyless = EncodeOptions { embed_key: None, ..options };
+        assert!(matches!(steg.encode_with(b"secret", "secret.txt", &carrier(), &keyless), Err(StegError::InvalidRequest(_))));
+        let misplaced = EncodeOptions { embed_key: key, ..Default::default() };
+        assert!(matches!(steg.encode_with(b"secret", "secret.txt", &carrier(), &misplaced), Err(StegError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn error_correction_repairs_damaged_pixels() {
+        let access = Arc::new(AccessControl::new(ClusterKey::generate()));
+        let steg = SomeImageSteganographer::new(75, 10).with_access_control(access);
+        let options = EncodeOptions { redundancy: Some(50), ..Default::default() };
+        let plain_fit = steg.check_fit_with(600, &carrier(), &EncodeOptions::default()).unwrap();
+        let fit = steg.check_fit_with(600, &carrier(), &options).unwrap();
+        assert!(fit.payload > plain_fit.payload * 3 / 2);
+
+        let secret: Vec<u8> = (0..600).map(|i| i as u8).collect();
+        let stego_image = steg.encode_with(&secret, "notes.bin", &carrier(), &options).unwrap();
+        assert!(stego::reveal(&stego_image).unwrap().len() <= fit.payload);
+        // Scribble over a band of pixels, and flip a bit of the length prefix
+        let mut stego = image::load_from_memory(&stego_image).unwrap().to_rgba();
+        for x in 0..64 {
+            *stego.get_pixel_mut(x, 20) = image::Rgba([255, 0, 255, 255]);
+        }
+        stego.get_pixel_mut(0, 0).data[0] ^= 1;
+        let mut damaged = Vec::new();
+        DynamicImage::ImageRgba8(stego).write_to(&mut damaged, image::ImageOutputFormat::PNG).unwrap();
+        assert_eq!(steg.decode_with(&damaged, &DecodeOptions::default()).unwrap(), secret);
+        let report = steg.decode_with_report(&damaged, &DecodeOptions::default()).unwrap();
+        assert!(report.corrected_bytes > 0 && report.repaired_shards > 0);
+        assert_eq!(steg.verify(&damaged).unwrap().integrity, provenance::Integrity::Intact);
+
+        let too_much = EncodeOptions { redundancy: Some(101), ..Default::default() };
+        assert!(matches!(steg.check_fit_with(6, &carrier(), &too_much), Err(StegError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn probes_turn_away_images_without_a_payload() {
+        let users = Arc::new(crate::users::UserDirectory::parse(r#"
+            [users.carol]
+            permissions = ["decode"]
+            max_requests = 1
+        "#).unwrap());
+        let steg = SomeImageSteganographer::new(75, 10);
+        let stego_image = steg.encode(b"secret", "secret.txt", &carrier()).unwrap();
+        let probed = steg.probe(&stego_image, &DecodeOptions::default()).unwrap();
+        assert_eq!((probed.present, probed.mode, probed.layer), (true, Some(Mode::Lsb), Some(probe::Layer::Container)));
+        assert_eq!(probed.declared_size, Some(stego::reveal(&stego_image).unwrap().len() as u64));
+
+        let carol = SomeImageSteganographer::new(75, 10).with_session(users.session(Some("carol".to_string())).unwrap());
+        let Carrier::Image(plain) = carrier() else { unreachable!() };
+        for _ in 0..3 {
+            assert_eq!(carol.decode(&plain), Err(StegError::NoPayload));
+        }
+        assert_eq!(carol.decode(&stego_image).unwrap(), b"secret");
+
+        let mut payload = stego::reveal(&stego_image).unwrap();
+        payload[4] = container::VERSION + 1;
+        let newer = stego::hide(&image::load_from_memory(&plain).unwrap(), &payload).unwrap();
+        assert_eq!(steg.decode(&newer), Err(StegError::UnsupportedVersion(container::VERSION + 1)));
+    }
+
+    #[test]
+    fn folders_spread_over_as_many_carriers_as_needed() {
+        let photo = |name: &str| HiddenFile { name: name.to_string(), content: vec![7; 700], ..Default::default() };
+        let note = HiddenFile { name: "note.txt".to_string(), content: b"from the trip".to_vec(), ..Default::default() };
+        let folder = vec![photo("beach.jpg"), photo("dunes.jpg"), note];
+        let steg = SomeImageSteganographer::new(75, 10);
+        let options = EncodeOptions { encryption: Some(Encryption::Key([5; 32])), ..Default::default() };
+
+        let stego_images = steg.encode_files(&folder, &[carrier()], &options).unwrap();
+        assert_eq!(stego_images.len(), 2);
+        let decode = DecodeOptions { encryption: options.encryption.clone(), ..Default::default() };
+        let bundle = steg.decode_files(&[stego_images[1].clone(), stego_images[0].clone()], &decode).unwrap();
+        assert_eq!(bundle.files.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["beach.jpg", "dunes.jpg", "note.txt"]);
+        assert_eq!(bundle.files[2].mime, "text/plain");
+        assert_eq!(bundle.files[0].content, vec![7; 700]);
+        let partial = steg.decode_files(&stego_images[1..], &decode).unwrap();
+        assert_eq!(partial.missing().map(|e| e.part).collect::<Vec<_>>(), [0]);
+        assert!(matches!(steg.decode_with(&stego_images[0], &decode), Err(StegError::InvalidRequest(_))));
+
+        let single = steg.encode(b"secret", "secret.txt", &carrier()).unwrap();
+        assert!(matches!(steg.decode_files(&[single.clone(), stego_images[0].clone()], &decode), Err(StegError::InvalidRequest(_))));
+        assert_eq!(steg.decode_files(&[single], &DecodeOptions::default()).unwrap().files[0].content, b"secret");
+        let big = vec![photo("huge.raw"), HiddenFile { content: vec![0; 2000], ..photo("huger.raw") }];
+        assert!(matches!(steg.encode_files(&big, &[carrier()], &options), Err(StegError::PayloadTooLarge { .. })));
+    }
+
+    #[test]
+    fn captions_hide_as_text() {
+        let steg = SomeImageSteganographer::new(75, 10);
+        let options = EncodeOptions { encryption: Some(Encryption::Password("hunter2".to_string())), ..Default::default() };
+        let caption = "Sunset at the dunes 🌅, día 3";
+        let stego_image = steg.hide_text(caption, &carrier(), &options).unwrap();
+        let decode = DecodeOptions { encryption: options.encryption.clone(), ..Default::default() };
+        assert_eq!(steg.reveal_text(&stego_image, &decode).unwrap(), caption);
+        assert_eq!(steg.reveal_text(&stego_image, &DecodeOptions::default()), Err(StegError::KeyRequired));
+        assert_eq!(steg.decode_file(&stego_image, &decode).unwrap().name, MESSAGE_NAME);
+
+        let capacity = steg.text_capacity(&carrier(), &EncodeOptions::default()).unwrap();
+        assert_eq!((capacity.ascii_chars, capacity.chars), (capacity.bytes, capacity.bytes / 4));
+        let longest = "a".repeat(capacity.ascii_chars as usize);
+        assert!(steg.hide_text(&longest, &carrier(), &EncodeOptions::default()).is_ok());
+        let emoji = "🌅".repeat(capacity.chars as usize);
+        assert!(steg.hide_text(&emoji, &carrier(), &EncodeOptions::default()).is_ok());
+        assert!(matches!(
+            steg.hide_text(&format!("{}a", longest), &carrier(), &EncodeOptions::default()),
+            Err(StegError::PayloadTooLarge { .. })
+        ));
+
+        let binary = steg.encode(&[0xff, 0xfe, 0x00], "blob.bin", &carrier()).unwrap();
+        assert!(matches!(steg.reveal_text(&binary, &DecodeOptions::default()), Err(StegError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn animations_stay_animated() {
+        let steg = SomeImageSteganographer::new(75, 10);
+        let gif = Carrier::Image(animation::sample(64, 4));
+        let options = EncodeOptions { encryption: Some(Encryption::Key([3; 32])), ..Default::default() };
+        let fit = steg.check_fit_with(40, &gif, &options).unwrap();
+        assert!(fit.fits);
+        assert_eq!(fit.capacity(), Animation::read(&animation::sample(64, 4)).unwrap().capacity());
+
+        let report = steg.encode_with_report(b"party.gif, but with a secret", "secret.txt", &gif, &options).unwrap();
+        assert!(animation::is_gif(&report.stego_image));
+        assert_eq!(report.quality.capacity, fit.capacity());
+        let stego = Animation::read(&report.stego_image).unwrap();
+        assert_eq!(stego.frames.iter().map(|frame| frame.delay).collect::<Vec<_>>(), vec![5, 6, 7, 8]);
+
+        let decode = DecodeOptions { encryption: options.encryption.clone(), ..Default::default() };
+        assert_eq!(steg.decode_with(&report.stego_image, &decode).unwrap(), b"party.gif, but with a secret");
+        assert_eq!(steg.probe(&report.stego_image, &decode).unwrap().mode, Some(Mode::Lsb));
+        assert_eq!(steg.decode_with(&animation::sample(64, 4), &decode), Err(StegError::NoPayload));
+        let dct = EncodeOptions { mode: Mode::Dct, ..Default::default() };
+        assert!(matches!(steg.encode_with(b"secret", "secret.txt", &gif, &dct), Err(StegError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn capped_calls_hide_the_same_way() {
+        let pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+        let capped = SomeImageSteganographer::new(75, 10).with_pool(pool);
+        let stego_image = capped.encode(b"one core is enough", "note.txt", &carrier()).unwrap();
+        assert_eq!(stego_image, SomeImageSteganographer::new(75, 10).encode(b"one core is enough", "note.txt", &carrier()).unwrap());
+        assert_eq!(capped.decode(&stego_image).unwrap(), b"one core is enough");
+    }
+}
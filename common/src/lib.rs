@@ -0,0 +1,29 @@
+//! Protocol shared by the client and the service provider nodes: the remote-trait-object
+//! service traits, the leader election wire messages and the Quinn transport they run on.
+//!
+//! Both binaries depend on this crate so the two sides of a connection are always built
+//! from the same contract.
+
+pub mod access;
+pub mod animation;
+pub mod audio;
+pub mod bundle;
+pub mod carriers;
+pub mod certs;
+pub mod container;
+pub mod crypto;
+pub mod dct;
+pub mod election;
+pub mod error;
+pub mod fec;
+pub mod http09;
+pub mod image_steganographer;
+pub mod mux;
+pub mod preview;
+pub mod probe;
+pub mod provenance;
+pub mod quality;
+pub mod quinn_utils;
+pub mod stego;
+pub mod transport;
+pub mod users;
@@ -0,0 +1,155 @@
+//! Serving several protocols on one QUIC endpoint.
+//!
+//! Every client asks for exactly one application protocol during the handshake (ALPN). The
+//! [`Router`] accepts connections on a shared endpoint and hands each one to the handler
+//! registered for the protocol that was negotiated, so a node needs a single UDP port.
+
+use std::collections::HashMap;
+
+use quinn::{Connection, Endpoint};
+use tokio::sync::mpsc;
+
+/// Leader election messages between service provider nodes.
+pub const ALPN_ELECTION: &[u8] = b"p2p-election/1";
+/// The remote-trait-object steganography service.
+pub const ALPN_STEG: &[u8] = b"p2p-steg/1";
+/// HTTP/0.9 file serving, see [`crate::http09`].
+pub const ALPN_QUIC_HTTP: &[u8] = b"hq-29";
+
+/// Number of established connections a handler may fall behind before new ones are refused.
+const BACKLOG: usize = 64;
+
+/// Protocol negotiated on `conn`, `None` if the peer did not ask for one.
+pub fn negotiated_protocol(conn: &Connection) -> Option<Vec<u8>> {
+    conn.handshake_data()?
+        .downcast::<quinn::crypto::rustls::HandshakeData>()
+        .ok()?
+        .protocol
+}
+
+#[derive(Clone)]
+struct Route {
+    sender: mpsc::Sender<Connection>,
+    admit: fn(&Connection) -> bool,
+}
+
+/// Dispatches the connections of one endpoint by ALPN.
+#[derive(Default)]
+pub struct Router {
+    routes: HashMap<Vec<u8>, Route>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a handler for `protocol` and returns the queue its connections arrive on.
+    pub fn route(&mut self, protocol: &[u8]) -> mpsc::Receiver<Connection> {
+        self.route_only(protocol, |_| true)
+    }
+
+    /// Like [`Router::route`], but connections for which `admit` is false are closed instead
+    /// of being handed on.
+    pub fn route_only(&mut self, protocol: &[u8], admit: fn(&Connection) -> bool) -> mpsc::Receiver<Connection> {
+        let (sender, receiver) = mpsc::channel(BACKLOG);
+        self.routes.insert(protocol.to_vec(), Route { sender, admit });
+        receiver
+    }
+
+    /// The protocols to offer in the endpoint's TLS config.
+    pub fn protocols(&self) -> Vec<&[u8]> {
+        let mut protocols: Vec<&[u8]> = self.routes.keys().map(Vec::as_slice).collect();
+        protocols.sort_unstable();
+        protocols
+    }
+
+    /// Accepts connections on `endpoint` until it is closed, completing each handshake in
+    /// its own task so a slow client does not hold up the others.
+    pub async fn serve(self, endpoint: Endpoint) {
+        while let Some(incoming) = endpoint.accept().await {
+            let routes = self.routes.clone();
+            tokio::spawn(async move {
+                let conn = match incoming.await {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        eprintln!("Failed to accept incoming connection: {}", e);
+                        return;
+                    }
+                };
+                let protocol = negotiated_protocol(&conn).unwrap_or_default();
+                match routes.get(&protocol) {
+                    Some(route) if !(route.admit)(&conn) => {
+                        eprintln!("Refused {} connection from {}", String::from_utf8_lossy(&protocol), conn.remote_address());
+                        conn.close(0u32.into(), b"forbidden");
+                    }
+                    Some(route) => {
+                        if route.sender.try_send(conn.clone()).is_err() {
+                            eprintln!("No capacity for {} connection from {}", String::from_utf8_lossy(&protocol), conn.remote_address());
+                            conn.close(0u32.into(), b"busy");
+                        }
+                    }
+                    None => {
+                        eprintln!("No handler for protocol {:?} from {}", String::from_utf8_lossy(&protocol), conn.remote_address());
+                        conn.close(0u32.into(), b"unsupported protocol");
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quinn_utils::{configure_client_tls, make_server_endpoint, server_name, TlsConfig};
+
+    async fn dial(addr: std::net::SocketAddr, protocol: &[u8]) -> Result<Connection, String> {
+        let mut client = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client.set_default_client_config(configure_client_tls(&TlsConfig::Insecure, protocol).unwrap());
+        client.connect(addr, &server_name(&addr)).unwrap().await.map_err(|e| e.to_string())
+    }
+
+    #[tokio::test]
+    async fn connections_are_routed_by_protocol() {
+        let mut router = Router::new();
+        let mut election = router.route(ALPN_ELECTION);
+        let mut steg = router.route(ALPN_STEG);
+        let (endpoint, _) = make_server_endpoint("127.0.0.1:0".parse().unwrap(), &TlsConfig::Insecure, &router.protocols()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        tokio::spawn(router.serve(endpoint));
+
+        let _to_steg = dial(addr, ALPN_STEG).await.unwrap();
+        let routed = steg.recv().await.unwrap();
+        assert_eq!(negotiated_protocol(&routed).as_deref(), Some(ALPN_STEG));
+
+        let _to_election = dial(addr, ALPN_ELECTION).await.unwrap();
+        let routed = election.recv().await.unwrap();
+        assert_eq!(negotiated_protocol(&routed).as_deref(), Some(ALPN_ELECTION));
+        assert!(steg.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn unknown_protocols_fail_the_handshake() {
+        let mut router = Router::new();
+        let _steg = router.route(ALPN_STEG);
+        let (endpoint, _) = make_server_endpoint("127.0.0.1:0".parse().unwrap(), &TlsConfig::Insecure, &router.protocols()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        tokio::spawn(router.serve(endpoint));
+
+        assert!(dial(addr, ALPN_QUIC_HTTP).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn refused_connections_are_closed() {
+        let mut router = Router::new();
+        let mut election = router.route_only(ALPN_ELECTION, |_| false);
+        let (endpoint, _) = make_server_endpoint("127.0.0.1:0".parse().unwrap(), &TlsConfig::Insecure, &router.protocols()).unwrap();
+        let addr = endpoint.local_addr().unwrap();
+        tokio::spawn(router.serve(endpoint));
+
+        let conn = dial(addr, ALPN_ELECTION).await.unwrap();
+        assert!(matches!(conn.closed().await, quinn::ConnectionError::ApplicationClosed(close) if &close.reason[..] == b"forbidden"));
+        assert!(election.try_recv().is_err());
+    }
+}
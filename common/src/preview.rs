@@ -0,0 +1,151 @@
+//! Low-resolution previews that owners can publish while the full image stays hidden.
+//!
+//! The image is scaled down to fit a square of [`PreviewOptions::max_size`] pixels, keeping
+//! its aspect ratio, then optionally blurred and stamped with a watermark image in the bottom
+//! right corner. Previews are always JPEG.
+
+use image::{imageops, DynamicImage, GenericImageView, ImageOutputFormat};
+use serde::{Deserialize, Serialize};
+
+/// Largest preview side that may be requested.
+pub const MAX_SIZE: u32 = 1024;
+/// Strongest blur that may be requested, as the Gaussian sigma in preview pixels.
+pub const MAX_BLUR: f32 = 20.0;
+// The watermark covers at most this share of the preview's width
+const WATERMARK_SHARE: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PreviewOptions {
+    /// Longest side of the preview in pixels. Images smaller than that are not enlarged.
+    pub max_size: u32,
+    /// JPEG quality of the preview (1-100).
+    pub quality: u8,
+    /// Gaussian blur sigma, 0 for a sharp preview.
+    #[serde(default)]
+    pub blur: f32,
+    /// Image stamped into the bottom right corner, e.g. the owner's logo.
+    #[serde(default)]
+    pub watermark: Option<Vec<u8>>,
+    /// How strongly the watermark shows, from 0 (invisible) to 1 (opaque).
+    #[serde(default = "default_opacity")]
+    pub watermark_opacity: f32,
+}
+
+fn default_opacity() -> f32 {
+    0.5
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        Self { max_size: 128, quality: 60, blur: 0.0, watermark: None, watermark_opacity: default_opacity() }
+    }
+}
+
+impl PreviewOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_size == 0 || self.max_size > MAX_SIZE {
+            return Err(format!("preview size must be between 1 and {} pixels", MAX_SIZE));
+        }
+        if !(0.0..=MAX_BLUR).contains(&self.blur) {
+            return Err(format!("blur must be between 0 and {}", MAX_BLUR));
+        }
+        if !(0.0..=1.0).contains(&self.watermark_opacity) {
+            return Err("watermark opacity must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Renders a preview of `image`. `watermark` is the decoded [`PreviewOptions::watermark`].
+pub fn render(image: &DynamicImage, watermark: Option<&DynamicImage>, options: &PreviewOptions) -> Result<Vec<u8>, String> {
+    options.validate()?;
+    let (width, height) = image.dimensions();
+    let mut preview = if width.max(height) > options.max_size {
+        image.thumbnail(options.max_size, options.max_size)
+    } else {
+        image.clone()
+    };
+    if options.blur > 0.0 {
+        preview = preview.blur(options.blur);
+    }
+
+    let mut preview = preview.to_rgba();
+    if let Some(watermark) = watermark {
+        let side = (preview.width() / WATERMARK_SHARE).max(1);
+        let mut stamp = watermark.thumbnail(side, side).to_rgba();
+        for pixel in stamp.pixels_mut() {
+            pixel[3] = (f32::from(pixel[3]) * options.watermark_opacity).round() as u8;
+        }
+        let x = preview.width().saturating_sub(stamp.width());
+        let y = preview.height().saturating_sub(stamp.height());
+        imageops::overlay(&mut preview, &stamp, x, y);
+    }
+
+    let mut buffer = Vec::new();
+    DynamicImage::ImageRgba8(preview)
+        .write_to(&mut buffer, ImageOutputFormat::JPEG(options.quality.clamp(1, 100)))
+        .map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn checkerboard(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            if (x + y) % 2 == 0 { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 255]) }
+        }))
+    }
+
+    fn contrast(image: &DynamicImage) -> i32 {
+        let luma = image.to_luma();
+        let values: Vec<i32> = luma.pixels().map(|p| i32::from(p[0])).collect();
+        values.iter().max().unwrap() - values.iter().min().unwrap()
+    }
+
+    #[test]
+    fn previews_are_small_and_keep_their_shape() {
+        let options = PreviewOptions { max_size: 64, ..Default::default() };
+        let preview = image::load_from_memory(&render(&checkerboard(400, 200), None, &options).unwrap()).unwrap();
+        assert_eq!(preview.dimensions(), (64, 32));
+        let small = image::load_from_memory(&render(&checkerboard(20, 10), None, &options).unwrap()).unwrap();
+        assert_eq!(small.dimensions(), (20, 10));
+    }
+
+    #[test]
+    fn blur_and_watermark_are_applied() {
+        let stripes = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, _| {
+            if (x / 4) % 2 == 0 { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 255]) }
+        }));
+        let sharp = PreviewOptions { max_size: 64, quality: 100, ..Default::default() };
+        let preview = |image: &DynamicImage, watermark, options: &PreviewOptions| {
+            image::load_from_memory(&render(image, watermark, options).unwrap()).unwrap()
+        };
+        let blurred = PreviewOptions { blur: 8.0, ..sharp.clone() };
+        assert!(contrast(&preview(&stripes, None, &blurred)) < contrast(&preview(&stripes, None, &sharp)) / 4);
+
+        let black = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 64, Rgba([0, 0, 0, 255])));
+        let logo = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 8, Rgba([255, 255, 255, 255])));
+        let opaque = PreviewOptions { watermark_opacity: 1.0, ..sharp.clone() };
+        let stamped = preview(&black, Some(&logo), &opaque).to_luma();
+        assert!(stamped.get_pixel(60, 60)[0] > 200);
+        assert!(stamped.get_pixel(40, 40)[0] < 50);
+        let faint = preview(&black, Some(&logo), &PreviewOptions { watermark_opacity: 0.25, ..sharp }).to_luma();
+        assert!((40..90).contains(&faint.get_pixel(60, 60)[0]));
+    }
+
+    #[test]
+    fn options_are_checked() {
+        for options in [
+            PreviewOptions { max_size: 0, ..Default::default() },
+            PreviewOptions { max_size: MAX_SIZE + 1, ..Default::default() },
+            PreviewOptions { blur: -1.0, ..Default::default() },
+            PreviewOptions { blur: f32::NAN, ..Default::default() },
+            PreviewOptions { watermark_opacity: 2.0, ..Default::default() },
+        ] {
+            assert!(render(&checkerboard(8, 8), None, &options).is_err());
+        }
+    }
+}
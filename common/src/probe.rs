@@ -0,0 +1,172 @@
+//! A cheap look at what a stego image carries. Only the length prefix and the first bytes of
+//! the payload are read; nothing is repaired, decrypted or checked, so probing costs little
+//! and reveals nothing of the secret.
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::{self, Animation};
+use crate::dct::{self, DctParams};
+use crate::stego::{self, EmbedKey, Mode};
+use crate::{access, bundle, container, crypto, fec, provenance};
+
+// Every layer starts with a four byte magic, most are followed by their version. Error
+// correction repeats its header, so a bit more is read to find a copy that is intact.
+const VERSION_AT: usize = 4;
+const PEEK_LEN: usize = 64;
+
+/// The outermost layer of a payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Layer {
+    ErrorCorrection,
+    Signature,
+    AccessPolicy,
+    Encryption,
+    Container,
+    Bundle,
+}
+
+impl Layer {
+    fn of(header: &[u8]) -> Option<Self> {
+        [
+            (fec::is_protected as fn(&[u8]) -> bool, Layer::ErrorCorrection),
+            (provenance::is_signed, Layer::Signature),
+            (access::is_protected, Layer::AccessPolicy),
+            (crypto::is_sealed, Layer::Encryption),
+            (container::is_container, Layer::Container),
+            (bundle::is_bundle, Layer::Bundle),
+        ]
+        .into_iter()
+        .find_map(|(is, layer)| is(header).then_some(layer))
+    }
+
+    /// Format version of this layer that this build reads. Access policies have none.
+    pub fn version(self) -> Option<u8> {
+        match self {
+            Layer::ErrorCorrection => Some(fec::VERSION),
+            Layer::Signature => Some(provenance::VERSION),
+            Layer::AccessPolicy => None,
+            Layer::Encryption => Some(crypto::VERSION),
+            Layer::Container => Some(container::VERSION),
+            Layer::Bundle => Some(bundle::VERSION),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probe {
+    /// Whether the image carries a payload hidden by this service. The other fields are only
+    /// set if it does.
+    pub present: bool,
+    pub mode: Option<Mode>,
+    pub layer: Option<Layer>,
+    /// Format version of the outermost layer, for layers that have one.
+    pub version: Option<u8>,
+    /// Payload size in bytes, as declared in front of the payload.
+    pub declared_size: Option<u64>,
+}
+
+impl Probe {
+    fn absent() -> Self {
+        Self { present: false, mode: None, layer: None, version: None, declared_size: None }
+    }
+
+    /// Whether this build can read the outermost layer of the payload.
+    pub fn supported(&self) -> bool {
+        match (self.layer.and_then(Layer::version), self.version) {
+            _ if !self.present => false,
+            (Some(known), Some(found)) => known == found,
+            _ => true,
+        }
+    }
+}
+
+// What a peek in `mode` tells, if it found a layer of ours
+fn found(mode: Mode, peeked: Result<(usize, Vec<u8>), String>) -> Option<Probe> {
+    let (len, header) = peeked.ok()?;
+    let layer = Layer::of(&header)?;
+    // Not known if the first copy of an error correction header is damaged
+    let version = match layer.version() {
+        Some(_) if Layer::of(header.get(..VERSION_AT).unwrap_or_default()) == Some(layer) => header.get(VERSION_AT).copied(),
+        _ => None,
+    };
+    Some(Probe {
+        present: true,
+        mode: Some(mode),
+        layer: Some(layer),
+        version,
+        declared_size: Some(len as u64),
+    })
+}
+
+/// Probes `stego` in every mode, in [`Mode::Scattered`] only if `key` is given.
+pub fn probe_image(stego: &DynamicImage, params: &DctParams, key: Option<&EmbedKey>) -> Probe {
+    let scattered = key.map(|key| (Mode::Scattered, stego::peek_scattered(stego, key, PEEK_LEN)));
+    let candidates = scattered.into_iter().chain([
+        (Mode::Dct, dct::peek(stego, params, PEEK_LEN)),
+        (Mode::Lsb, stego::peek(stego, PEEK_LEN)),
+    ]);
+    candidates.filter_map(|(mode, peeked)| found(mode, peeked)).next().unwrap_or_else(Probe::absent)
+}
+
+/// Probes an animated GIF. Animations only carry payloads in [`Mode::Lsb`].
+pub fn probe_animation(stego: &Animation) -> Probe {
+    found(Mode::Lsb, animation::peek(stego, PEEK_LEN)).unwrap_or_else(Probe::absent)
+}
+
+/// [`probe_image`] for an encoded stego image, or [`probe_animation`] if it is a GIF.
+pub fn probe(stego_image: &[u8], params: &DctParams, key: Option<&EmbedKey>) -> Result<Probe, String> {
+    if animation::is_gif(stego_image) {
+        let stego = Animation::read(stego_image).map_err(|e| format!("invalid stego animation: {}", e))?;
+        return Ok(probe_animation(&stego));
+    }
+    let stego = image::load_from_memory(stego_image).map_err(|e| format!("invalid stego image: {}", e))?;
+    Ok(probe_image(&stego, params, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: DctParams = DctParams { quality: 75, max_pixel_diff: 10 };
+
+    fn carrier() -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(96, 96, image::Rgb([120, 130, 140])))
+    }
+
+    #[test]
+    fn headers_are_read_in_every_mode() {
+        let signed = provenance::sign(&crate::certs::ClusterKey::generate(), b"P2PC\x01...", 0);
+        let lsb = stego::hide(&carrier(), &signed).unwrap();
+        assert_eq!(probe(&lsb, &PARAMS, None).unwrap(), Probe {
+            present: true,
+            mode: Some(Mode::Lsb),
+            layer: Some(Layer::Signature),
+            version: Some(provenance::VERSION),
+            declared_size: Some(signed.len() as u64),
+        });
+
+        let dct = stego::hide_with(&carrier(), b"P2PC\x09", Mode::Dct, &PARAMS).unwrap();
+        let probed = probe(&dct, &PARAMS, None).unwrap();
+        assert_eq!((probed.mode, probed.version, probed.supported()), (Some(Mode::Dct), Some(9), false));
+
+        let key = EmbedKey("probe".to_string());
+        let scattered = stego::hide_scattered(&carrier(), b"P2PA....", &key).unwrap();
+        assert!(!probe(&scattered, &PARAMS, None).unwrap().present);
+        let probed = probe(&scattered, &PARAMS, Some(&key)).unwrap();
+        assert_eq!((probed.layer, probed.version, probed.supported()), (Some(Layer::AccessPolicy), None, true));
+
+        let animated = animation::hide(&animation::sample(16, 3), b"P2PC\x01...").unwrap();
+        let probed = probe(&animated, &PARAMS, None).unwrap();
+        assert_eq!((probed.mode, probed.layer, probed.declared_size), (Some(Mode::Lsb), Some(Layer::Container), Some(8)));
+    }
+
+    #[test]
+    fn plain_images_carry_nothing() {
+        let plain = stego::hide(&carrier(), b"not one of ours").unwrap();
+        assert_eq!(probe(&plain, &PARAMS, None).unwrap(), Probe::absent());
+        assert!(!Probe::absent().supported());
+        assert!(probe(b"not an image", &PARAMS, None).is_err());
+        assert_eq!(probe(&animation::sample(8, 2), &PARAMS, None).unwrap(), Probe::absent());
+    }
+}
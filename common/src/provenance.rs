@@ -0,0 +1,147 @@
+//! Provenance signatures: proof that a payload was embedded by a node of this cluster and has
+//! not been changed since.
+//!
+//! A node holding the cluster key wraps every payload it embeds as the outermost layer:
+//!
+//! ```text
+//! P2PS | version (1) | key fingerprint (8) | signed at (u64 BE, Unix seconds)
+//!      | SHA-256 of the payload (32) | signature (64) | payload
+//! ```
+//!
+//! The signature covers everything before it. Checking it needs only the cluster's public key
+//! and never looks inside the payload, so the hidden file stays hidden.
+
+use ed25519_dalek::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::certs::{self, ClusterKey};
+
+const MAGIC: &[u8; 4] = b"P2PS";
+pub const VERSION: u8 = 1;
+const FINGERPRINT_LEN: usize = 8;
+const HASH_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+const SIGNED_LEN: usize = MAGIC.len() + 1 + FINGERPRINT_LEN + 8 + HASH_LEN;
+/// Bytes the signature layer adds to a payload.
+pub const OVERHEAD: usize = SIGNED_LEN + SIGNATURE_LEN;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Integrity {
+    /// Signed by the cluster and unchanged since.
+    Intact,
+    /// The signature holds, but the payload no longer matches its hash.
+    Tampered,
+    /// The header was changed after signing, or the signature is damaged.
+    BadSignature,
+    /// Signed with a key other than the one checked against.
+    ForeignSigner,
+    /// Nothing this cluster would have signed is hidden in the file.
+    Unsigned,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub integrity: Integrity,
+    /// Fingerprint of the key the payload claims to be signed with.
+    pub signer: Option<String>,
+    /// Seconds since the Unix epoch at which the payload was signed.
+    pub signed_at: Option<u64>,
+}
+
+/// Short hex id of a cluster key, as reported for signers.
+pub fn fingerprint(key: &VerifyingKey) -> String {
+    hex::encode(&Sha256::digest(key.as_bytes())[..FINGERPRINT_LEN])
+}
+
+pub fn is_signed(payload: &[u8]) -> bool {
+    payload.starts_with(MAGIC)
+}
+
+/// Puts a signature over `payload`, made at `now`, in front of it.
+pub fn sign(key: &ClusterKey, payload: &[u8], now: u64) -> Vec<u8> {
+    let mut signed = MAGIC.to_vec();
+    signed.push(VERSION);
+    signed.extend_from_slice(&Sha256::digest(key.verifying_key().as_bytes())[..FINGERPRINT_LEN]);
+    signed.extend_from_slice(&now.to_be_bytes());
+    signed.extend_from_slice(&Sha256::digest(payload));
+    let signature = key.sign(&signed);
+    signed.extend_from_slice(&signature);
+    signed.extend_from_slice(payload);
+    signed
+}
+
+/// The payload inside the signature layer, or all of `payload` if it is not signed.
+pub fn inner(payload: &[u8]) -> &[u8] {
+    if is_signed(payload) && payload.len() >= OVERHEAD {
+        &payload[OVERHEAD..]
+    } else {
+        payload
+    }
+}
+
+/// Checks the signature of `payload` against the cluster's public `key`.
+pub fn verify(payload: &[u8], key: &VerifyingKey) -> VerifyReport {
+    let report = |integrity, signer, signed_at| VerifyReport { integrity, signer, signed_at };
+    if !is_signed(payload) {
+        return report(Integrity::Unsigned, None, None);
+    }
+    if payload.len() < OVERHEAD || payload[MAGIC.len()] != VERSION {
+        return report(Integrity::BadSignature, None, None);
+    }
+    let (signed, rest) = payload.split_at(SIGNED_LEN);
+    let (signature, content) = rest.split_at(SIGNATURE_LEN);
+    let fingerprint_at = MAGIC.len() + 1;
+    let signer = hex::encode(&signed[fingerprint_at..fingerprint_at + FINGERPRINT_LEN]);
+    let time_at = fingerprint_at + FINGERPRINT_LEN;
+    let signed_at = u64::from_be_bytes(signed[time_at..time_at + 8].try_into().unwrap());
+
+    let integrity = if signer != fingerprint(key) {
+        Integrity::ForeignSigner
+    } else if !certs::verify_signature(key, signed, signature.try_into().unwrap()) {
+        Integrity::BadSignature
+    } else if Sha256::digest(content)[..] != signed[time_at + 8..] {
+        Integrity::Tampered
+    } else {
+        Integrity::Intact
+    };
+    report(integrity, Some(signer), Some(signed_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_hold_until_something_changes() {
+        let key = ClusterKey::generate();
+        let signed = sign(&key, b"sealed payload", 1_700_000_000);
+        assert_eq!(signed.len(), b"sealed payload".len() + OVERHEAD);
+        assert_eq!(inner(&signed), b"sealed payload");
+
+        let report = verify(&signed, &key.verifying_key());
+        assert_eq!(report.integrity, Integrity::Intact);
+        assert_eq!(report.signer, Some(fingerprint(&key.verifying_key())));
+        assert_eq!(report.signed_at, Some(1_700_000_000));
+
+        let mut tampered = signed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(verify(&tampered, &key.verifying_key()).integrity, Integrity::Tampered);
+        let mut backdated = signed.clone();
+        backdated[MAGIC.len() + 1 + FINGERPRINT_LEN + 7] ^= 1;
+        assert_eq!(verify(&backdated, &key.verifying_key()).integrity, Integrity::BadSignature);
+    }
+
+    #[test]
+    fn other_keys_and_unsigned_payloads_are_reported() {
+        let signed = sign(&ClusterKey::generate(), b"payload", 1);
+        let other = ClusterKey::generate().verifying_key();
+        assert_eq!(verify(&signed, &other).integrity, Integrity::ForeignSigner);
+        assert_eq!(verify(b"P2PC plain container", &other), VerifyReport {
+            integrity: Integrity::Unsigned,
+            signer: None,
+            signed_at: None,
+        });
+        assert_eq!(verify(&signed[..20], &other).integrity, Integrity::BadSignature);
+    }
+}
@@ -0,0 +1,155 @@
+//! How far a stego image is from its carrier.
+//!
+//! Metrics compare the carrier with the stego image as the client receives it, so the loss of
+//! a JPEG encoded [`crate::stego::Mode::Dct`] image counts as well. Alpha is ignored.
+
+use image::{DynamicImage, GrayImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+const WINDOW: u32 = 8;
+const WINDOW_STEP: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    /// Peak signal-to-noise ratio in dB over the colour channels; infinite if nothing changed.
+    pub psnr: f64,
+    /// Mean structural similarity of the luminance over 8x8 windows, 1.0 if nothing changed.
+    pub ssim: f64,
+    /// Largest change of any colour channel of any pixel.
+    pub max_delta: u8,
+    pub modified_pixels: u64,
+    pub total_pixels: u64,
+    /// Bytes hidden, including encryption and policy overhead.
+    pub payload: usize,
+    /// Bytes the carrier could have held in the mode used.
+    pub capacity: usize,
+}
+
+impl QualityReport {
+    /// Share of the carrier's capacity the payload takes up.
+    pub fn fill_ratio(&self) -> f64 {
+        if self.capacity == 0 {
+            return 1.0;
+        }
+        self.payload as f64 / self.capacity as f64
+    }
+}
+
+/// Compares `carrier` with `stego`, which must have the same dimensions.
+pub fn measure(carrier: &DynamicImage, stego: &DynamicImage, payload: usize, capacity: usize) -> Result<QualityReport, String> {
+    let (before, after) = (carrier.to_rgba(), stego.to_rgba());
+    if before.dimensions() != after.dimensions() {
+        return Err(format!(
+            "stego image is {}x{}, the carrier {}x{}",
+            after.width(), after.height(), before.width(), before.height()
+        ));
+    }
+
+    let mut squared_error = 0u64;
+    let mut max_delta = 0u8;
+    let mut modified_pixels = 0u64;
+    for (a, b) in before.pixels().zip(after.pixels()) {
+        let mut modified = false;
+        for c in 0..3 {
+            let delta = (a[c] as i16 - b[c] as i16).unsigned_abs() as u8;
+            squared_error += u64::from(delta) * u64::from(delta);
+            max_delta = max_delta.max(delta);
+            modified |= delta != 0;
+        }
+        modified_pixels += u64::from(modified);
+    }
+    let total_pixels = u64::from(before.width()) * u64::from(before.height());
+    let psnr = match squared_error {
+        0 => f64::INFINITY,
+        _ => {
+            let mse = squared_error as f64 / (total_pixels * 3) as f64;
+            10.0 * (255.0f64 * 255.0 / mse).log10()
+        }
+    };
+
+    Ok(QualityReport {
+        psnr,
+        ssim: ssim(&luma(&before), &luma(&after)),
+        max_delta,
+        modified_pixels,
+        total_pixels,
+        payload,
+        capacity,
+    })
+}
+
+fn luma(image: &RgbaImage) -> GrayImage {
+    DynamicImage::ImageRgba8(image.clone()).to_luma()
+}
+
+fn ssim(a: &GrayImage, b: &GrayImage) -> f64 {
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+    let (width, height) = a.dimensions();
+    // Images smaller than a window are compared as a whole
+    let (window_w, window_h) = (WINDOW.min(width), WINDOW.min(height));
+    if window_w == 0 || window_h == 0 {
+        return 1.0;
+    }
+
+    let mut total = 0.0;
+    let mut windows = 0;
+    for y in (0..=height - window_h).step_by(WINDOW_STEP as usize) {
+        for x in (0..=width - window_w).step_by(WINDOW_STEP as usize) {
+            let n = f64::from(window_w * window_h);
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for dy in 0..window_h {
+                for dx in 0..window_w {
+                    let pa = f64::from(a.get_pixel(x + dx, y + dy)[0]);
+                    let pb = f64::from(b.get_pixel(x + dx, y + dy)[0]);
+                    sum_a += pa;
+                    sum_b += pb;
+                    sum_aa += pa * pa;
+                    sum_bb += pb * pb;
+                    sum_ab += pa * pb;
+                }
+            }
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+            total += ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+    total / f64::from(windows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn carrier() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, y| Rgba([(x * 8) as u8, (y * 8) as u8, 100, 255])))
+    }
+
+    #[test]
+    fn identical_images_are_perfect() {
+        let report = measure(&carrier(), &carrier(), 10, 100).unwrap();
+        assert_eq!(report.psnr, f64::INFINITY);
+        assert!((report.ssim - 1.0).abs() < 1e-9);
+        assert_eq!((report.max_delta, report.modified_pixels, report.total_pixels), (0, 0, 1024));
+        assert!((report.fill_ratio() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn changes_are_measured() {
+        let mut stego = carrier().to_rgba();
+        stego.get_pixel_mut(0, 0)[0] ^= 1;
+        stego.get_pixel_mut(5, 5)[2] += 30;
+        let report = measure(&carrier(), &DynamicImage::ImageRgba8(stego), 0, 1).unwrap();
+        assert_eq!((report.max_delta, report.modified_pixels), (30, 2));
+        // MSE of 901 / 3072 samples
+        assert!((report.psnr - 10.0 * (255.0f64 * 255.0 * 3072.0 / 901.0).log10()).abs() < 1e-9);
+        assert!(report.ssim < 1.0 && report.ssim > 0.9);
+
+        assert!(measure(&carrier(), &DynamicImage::new_rgba8(8, 8), 0, 1).is_err());
+    }
+}
@@ -0,0 +1,594 @@
+//! QUIC endpoint and TLS helpers shared by the client and the service provider nodes.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+    time::Duration,
+};
+
+use quinn_proto::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, PrivatePkcs8KeyDer};
+use quinn::{Endpoint, ClientConfig, ServerConfig};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use crate::certs;
+
+pub fn strip_ipv6_brackets(host: &str) -> &str {
+    // An ipv6 url looks like eg https://[::1]:4433/Cargo.toml, wherein the host [::1] is the
+    // ipv6 address ::1 wrapped in brackets, per RFC 2732. This strips those.
+    if host.starts_with('[') && host.ends_with(']') {
+        &host[1..host.len() - 1]
+    } else {
+        host
+    }
+}
+
+pub fn duration_secs(x: &Duration) -> f32 {
+    x.as_secs() as f32 + x.subsec_nanos() as f32 * 1e-9
+}
+#[derive(Debug)]
+pub struct SkipServerVerification(Arc<rustls::crypto::CryptoProvider>);
+
+impl SkipServerVerification {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self(Arc::new(rustls::crypto::ring::default_provider())))
+    }
+}
+
+impl rustls::client::danger::ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+
+/// Server certificate fingerprints remembered by [`TofuServerVerification`], one
+/// `<host> sha256:<hex>` line per server. Lines starting with `#` are ignored.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct KnownHosts {
+    hosts: BTreeMap<String, String>,
+}
+
+impl KnownHosts {
+    pub fn parse(text: &str) -> Self {
+        let hosts = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                Some((fields.next()?.to_string(), fields.next()?.to_string()))
+            })
+            .collect();
+        Self { hosts }
+    }
+
+    /// Reads `path`, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self, std::io::Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), std::io::Error> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for (host, fingerprint) in &self.hosts {
+            text.push_str(&format!("{} {}\n", host, fingerprint));
+        }
+        fs::write(path, text)
+    }
+
+    pub fn get(&self, host: &str) -> Option<&str> {
+        self.hosts.get(host).map(String::as_str)
+    }
+
+    pub fn pin(&mut self, host: &str, fingerprint: &str) {
+        self.hosts.insert(host.to_string(), fingerprint.to_string());
+    }
+}
+
+/// `sha256:<hex>` fingerprint of a DER encoded certificate.
+pub fn fingerprint(cert: &CertificateDer<'_>) -> String {
+    let digest = Sha256::digest(cert.as_ref());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", hex)
+}
+
+/// Trust on first use: the first certificate a server presents is pinned in a known hosts
+/// file and later connections must present the same one. A changed certificate is refused
+/// unless its host is listed in `accept_changed`, in which case the new one is pinned.
+///
+/// Hosts are keyed by the server name that was dialed, so every port of a node shares a pin.
+#[derive(Debug)]
+pub struct TofuServerVerification {
+    known_hosts: PathBuf,
+    accept_changed: HashSet<String>,
+    provider: Arc<rustls::crypto::CryptoProvider>,
+}
+
+impl TofuServerVerification {
+    pub fn new(known_hosts: PathBuf, accept_changed: HashSet<String>) -> Arc<Self> {
+        Arc::new(Self {
+            known_hosts,
+            accept_changed,
+            provider: Arc::new(rustls::crypto::ring::default_provider()),
+        })
+    }
+
+    fn check(&self, host: &str, fingerprint: &str) -> Result<(), rustls::Error> {
+        // Every endpoint of the process has its own verifier on the same file.
+        static FILE_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let io_error = |e: std::io::Error| {
+            rustls::Error::General(format!("{}: {}", self.known_hosts.display(), e))
+        };
+        let mut known_hosts = KnownHosts::load(&self.known_hosts).map_err(io_error)?;
+        match known_hosts.get(host) {
+            Some(pinned) if pinned == fingerprint => return Ok(()),
+            Some(pinned) if !self.accept_changed.contains(host) => {
+                return Err(rustls::Error::General(format!(
+                    "certificate of {} changed from {} to {}; if this is expected, set P2P_TOFU_ACCEPT={} to pin the new one",
+                    host, pinned, fingerprint, host
+                )));
+            }
+            Some(pinned) => eprintln!("Re-pinning {}: {} replaces {}", host, fingerprint, pinned),
+            None => eprintln!("Pinning first certificate seen for {}: {}", host, fingerprint),
+        }
+        known_hosts.pin(host, fingerprint);
+        known_hosts.save(&self.known_hosts).map_err(io_error)
+    }
+}
+
+impl rustls::client::danger::ServerCertVerifier for TofuServerVerification {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        self.check(&server_name.to_str(), &fingerprint(end_entity))?;
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+
+pub fn make_client_endpoint(
+    bind_addr: SocketAddr,
+    server_certs: &[&[u8]],
+) -> Result<Endpoint, Box<dyn Error + Send + Sync + 'static>> {
+    let client_cfg = configure_client(server_certs)?;
+    let mut endpoint = Endpoint::client(bind_addr)?;
+    endpoint.set_default_client_config(client_cfg);
+    Ok(endpoint)
+}
+
+/// How endpoints authenticate each other.
+///
+/// Only [`TlsConfig::Cluster`] asks clients for a certificate, so it is the only mode in which
+/// a server can tell another node (see [`peer_is_node`]) from a user or a stranger. Nodes
+/// therefore route [`ALPN_ELECTION`](crate::mux::ALPN_ELECTION) and take part in leader
+/// elections in Cluster mode only; in the other modes each node leads on its own.
+#[derive(Clone, Debug)]
+pub enum TlsConfig {
+    /// Both ends present a certificate issued by the cluster CA and verify the other end
+    /// against that CA.
+    Cluster {
+        ca_cert: PathBuf,
+        cert: PathBuf,
+        key: PathBuf,
+    },
+    /// Trust on first use, for deployments without a CA. Servers present a self-signed
+    /// certificate that is created on first start and kept in `cert`/`key`; clients pin it in
+    /// `known_hosts` (see [`TofuServerVerification`]). Clients do not present certificates.
+    Tofu {
+        cert: PathBuf,
+        key: PathBuf,
+        known_hosts: PathBuf,
+        accept_changed: HashSet<String>,
+    },
+    /// Throwaway self-signed server certificates, no client certificates and no verification
+    /// at all. Development only.
+    Insecure,
+}
+
+impl TlsConfig {
+    /// Reads the cluster certificates from `P2P_CERT_DIR` (default `certs`), laid out the way
+    /// the `certgen` tool writes them. Setting `P2P_INSECURE_TLS=1` switches to [`TlsConfig::Insecure`].
+    ///
+    /// Setting `P2P_TOFU=1` switches to [`TlsConfig::Tofu`] with the node certificate files of
+    /// the same directory and pins kept in `P2P_KNOWN_HOSTS` (default `known_hosts` in that
+    /// directory). `P2P_TOFU_ACCEPT` is a comma separated list of hosts whose changed
+    /// certificate should be accepted and pinned.
+    pub fn from_env() -> Self {
+        if env::var("P2P_INSECURE_TLS").is_ok_and(|v| v == "1") {
+            return TlsConfig::Insecure;
+        }
+        let dir = PathBuf::from(env::var("P2P_CERT_DIR").unwrap_or_else(|_| "certs".to_string()));
+        if env::var("P2P_TOFU").is_ok_and(|v| v == "1") {
+            return TlsConfig::Tofu {
+                cert: dir.join(certs::NODE_CERT_FILE),
+                key: dir.join(certs::NODE_KEY_FILE),
+                known_hosts: env::var("P2P_KNOWN_HOSTS").map(PathBuf::from).unwrap_or_else(|_| dir.join("known_hosts")),
+                accept_changed: env::var("P2P_TOFU_ACCEPT")
+                    .map(|hosts| hosts.split(',').map(str::trim).filter(|h| !h.is_empty()).map(String::from).collect())
+                    .unwrap_or_default(),
+            };
+        }
+        TlsConfig::Cluster {
+            ca_cert: dir.join(certs::CA_CERT_FILE),
+            cert: dir.join(certs::NODE_CERT_FILE),
+            key: dir.join(certs::NODE_KEY_FILE),
+        }
+    }
+
+    /// Like [`TlsConfig::from_env`], but picks up the client certificate that identifies the
+    /// user instead of a node certificate.
+    pub fn client_from_env() -> Self {
+        match Self::from_env() {
+            TlsConfig::Cluster { ca_cert, .. } => {
+                let dir = ca_cert.parent().map(PathBuf::from).unwrap_or_default();
+                TlsConfig::Cluster {
+                    ca_cert,
+                    cert: dir.join(certs::CLIENT_CERT_FILE),
+                    key: dir.join(certs::CLIENT_KEY_FILE),
+                }
+            }
+            other => other,
+        }
+    }
+}
+
+fn warn_insecure() {
+    static WARNED: Once = Once::new();
+    WARNED.call_once(|| {
+        eprintln!("**********************************************************************");
+        eprintln!("** WARNING: TLS is running in INSECURE mode.");
+        eprintln!("** Server certificates are self-signed and are NOT verified, so any");
+        eprintln!("** host on the network can impersonate a node. Clients are not");
+        eprintln!("** authenticated either. Never use in production.");
+        eprintln!("**********************************************************************");
+    });
+}
+
+/// Server name to verify a peer certificate against when dialing `addr`.
+pub fn server_name(addr: &SocketAddr) -> String {
+    addr.ip().to_string()
+}
+
+/// User name from the client certificate the peer presented on `conn`, `None` if it did not
+/// present one.
+pub fn peer_identity(conn: &quinn::Connection) -> Option<String> {
+    let chain = conn.peer_identity()?.downcast::<Vec<CertificateDer<'static>>>().ok()?;
+    certs::common_name(chain.first()?)
+}
+
+/// Whether the peer on `conn` presented a node certificate, as opposed to a user's client
+/// certificate or none at all.
+pub fn peer_is_node(conn: &quinn::Connection) -> bool {
+    let chain = conn.peer_identity().and_then(|identity| identity.downcast::<Vec<CertificateDer<'static>>>().ok());
+    chain.is_some_and(|chain| chain.first().is_some_and(certs::is_node_cert))
+}
+
+/// Constructs a QUIC endpoint configured to listen for incoming connections on a certain address
+/// and port.
+///
+/// ## Returns
+///
+/// - a stream of incoming QUIC connections
+/// - server certificate serialized into DER format
+pub fn make_server_endpoint(
+    bind_addr: SocketAddr,
+    tls: &TlsConfig,
+    alpn: &[&[u8]],
+) -> Result<(Endpoint, CertificateDer<'static>), Box<dyn Error + Send + Sync + 'static>> {
+    let (server_config, server_cert) = configure_server(tls, alpn)?;
+    let endpoint = Endpoint::server(server_config, bind_addr)?;
+    Ok((endpoint, server_cert))
+}
+
+/// Builds default quinn client config and trusts given certificates.
+///
+/// ## Args
+///
+/// - server_certs: a list of trusted certificates in DER format.
+pub fn configure_client(
+    server_certs: &[&[u8]],
+) -> Result<ClientConfig, Box<dyn Error + Send + Sync + 'static>> {
+    let mut certs = rustls::RootCertStore::empty();
+    for cert in server_certs {
+        certs.add(CertificateDer::from(*cert))?;
+    }
+
+    Ok(ClientConfig::with_root_certificates(Arc::new(certs))?)
+}
+
+/// Builds the client config used to dial servers and peers, verifying them according to `tls`.
+///
+/// In cluster mode the configured certificate is presented as the client identity. `alpn`
+/// names the application protocol to ask the server for, see [`crate::mux`].
+pub fn configure_client_tls(
+    tls: &TlsConfig,
+    alpn: &[u8],
+) -> Result<ClientConfig, Box<dyn Error + Send + Sync + 'static>> {
+    let mut crypto = match tls {
+        TlsConfig::Cluster { ca_cert, cert, key } => rustls::ClientConfig::builder()
+            .with_root_certificates(certs::load_root_store(ca_cert)?)
+            .with_client_auth_cert(certs::load_certs(cert)?, certs::load_private_key(key)?)?,
+        TlsConfig::Tofu { known_hosts, accept_changed, .. } => rustls::ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(TofuServerVerification::new(known_hosts.clone(), accept_changed.clone()))
+            .with_no_client_auth(),
+        TlsConfig::Insecure => {
+            warn_insecure();
+            rustls::ClientConfig::builder()
+                .dangerous()
+                .with_custom_certificate_verifier(SkipServerVerification::new())
+                .with_no_client_auth()
+        }
+    };
+    crypto.alpn_protocols = vec![alpn.to_vec()];
+
+    Ok(ClientConfig::new(Arc::new(QuicClientConfig::try_from(crypto)?)))
+}
+
+/// Returns server configuration along with its certificate.
+///
+/// In cluster mode the certificate chain and key are loaded from the configured files and
+/// clients must present a certificate issued by the cluster CA; otherwise a self-signed
+/// certificate for "localhost" is minted and clients stay anonymous. In TOFU mode the
+/// self-signed certificate is kept on disk so clients can pin it.
+///
+/// `alpn` lists the application protocols the endpoint serves, see [`crate::mux`].
+pub fn configure_server(
+    tls: &TlsConfig,
+    alpn: &[&[u8]],
+) -> Result<(ServerConfig, CertificateDer<'static>), Box<dyn Error + Send + Sync + 'static>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])?;
+    let (mut crypto, cert_der) = match tls {
+        TlsConfig::Cluster { ca_cert, cert, key } => {
+            let cert_chain = certs::load_certs(cert)?;
+            let cert_der = cert_chain[0].clone();
+            let client_verifier = rustls::server::WebPkiClientVerifier::builder_with_provider(
+                Arc::new(certs::load_root_store(ca_cert)?),
+                provider,
+            )
+            .build()?;
+            let crypto = builder
+                .with_client_cert_verifier(client_verifier)
+                .with_single_cert(cert_chain, certs::load_private_key(key)?)?;
+            (crypto, cert_der)
+        }
+        TlsConfig::Tofu { cert, key, .. } => {
+            let (cert_der, priv_key) = certs::load_or_create_self_signed(cert, key)?;
+            (builder.with_no_client_auth().with_single_cert(vec![cert_der.clone()], priv_key)?, cert_der)
+        }
+        TlsConfig::Insecure => {
+            warn_insecure();
+            let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()])?;
+            let cert_der = CertificateDer::from(cert.cert);
+            let priv_key: PrivateKeyDer<'static> = PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()).into();
+            (builder.with_no_client_auth().with_single_cert(vec![cert_der.clone()], priv_key)?, cert_der)
+        }
+    };
+    crypto.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+
+    let mut server_config = ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(crypto)?));
+    let transport_config = Arc::get_mut(&mut server_config.transport).unwrap();
+    transport_config.max_concurrent_uni_streams(0_u8.into());
+
+    Ok((server_config, cert_der))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mux::ALPN_STEG;
+    use std::path::Path;
+
+    fn write_bundle(dir: &Path, ca: &certs::IssuedCert) -> TlsConfig {
+        let node = certs::generate_node_cert(ca, "node1", &["127.0.0.1".to_owned()]).unwrap();
+        node.write(dir, certs::NODE_CERT_FILE, certs::NODE_KEY_FILE).unwrap();
+        std::fs::write(dir.join(certs::CA_CERT_FILE), &ca.cert_pem).unwrap();
+        TlsConfig::Cluster {
+            ca_cert: dir.join(certs::CA_CERT_FILE),
+            cert: dir.join(certs::NODE_CERT_FILE),
+            key: dir.join(certs::NODE_KEY_FILE),
+        }
+    }
+
+    fn client_bundle(dir: &Path, ca: &certs::IssuedCert, user: &str) -> TlsConfig {
+        certs::generate_client_cert(ca, user).unwrap()
+            .write(dir, certs::CLIENT_CERT_FILE, certs::CLIENT_KEY_FILE).unwrap();
+        TlsConfig::Cluster {
+            ca_cert: dir.join(certs::CA_CERT_FILE),
+            cert: dir.join(certs::CLIENT_CERT_FILE),
+            key: dir.join(certs::CLIENT_KEY_FILE),
+        }
+    }
+
+    async fn handshake(server_tls: &TlsConfig, client_tls: &TlsConfig) -> Result<Option<String>, String> {
+        let (server, _) = make_server_endpoint("127.0.0.1:0".parse().unwrap(), server_tls, &[ALPN_STEG]).unwrap();
+        let addr = server.local_addr().unwrap();
+        let mut client = Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client.set_default_client_config(configure_client_tls(client_tls, ALPN_STEG).unwrap());
+
+        let accepted = tokio::spawn(async move {
+            let conn = server.accept().await.unwrap().await.map_err(|e| e.to_string())?;
+            Ok::<_, String>(peer_identity(&conn))
+        });
+        let conn = client.connect(addr, &server_name(&addr)).unwrap().await.map_err(|e| e.to_string());
+        let identity = accepted.await.unwrap();
+        drop(conn);
+        identity
+    }
+
+    #[tokio::test]
+    async fn server_learns_the_client_user() {
+        let dir = std::env::temp_dir().join(format!("p2p-mtls-{}", rand::random::<u64>()));
+        let ca = certs::generate_ca("test ca").unwrap();
+        let server_tls = write_bundle(&dir, &ca);
+        let client_tls = client_bundle(&dir, &ca, "alice");
+
+        assert_eq!(handshake(&server_tls, &client_tls).await.unwrap().as_deref(), Some("alice"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn server_rejects_clients_of_other_cas() {
+        let dir = std::env::temp_dir().join(format!("p2p-mtls-{}", rand::random::<u64>()));
+        let ca = certs::generate_ca("test ca").unwrap();
+        let server_tls = write_bundle(&dir, &ca);
+
+        let other_dir = dir.join("other");
+        let other_ca = certs::generate_ca("other ca").unwrap();
+        std::fs::create_dir_all(&other_dir).unwrap();
+        std::fs::write(other_dir.join(certs::CA_CERT_FILE), &ca.cert_pem).unwrap();
+        let client_tls = client_bundle(&other_dir, &other_ca, "mallory");
+
+        assert!(handshake(&server_tls, &client_tls).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn self_signed() -> CertificateDer<'static> {
+        CertificateDer::from(rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap().cert)
+    }
+
+    fn verify_tofu(verifier: &TofuServerVerification, host: &str, cert: &CertificateDer<'_>) -> Result<(), rustls::Error> {
+        use rustls::client::danger::ServerCertVerifier;
+        let name = ServerName::try_from(host.to_owned()).unwrap();
+        verifier.verify_server_cert(cert, &[], &name, &[], UnixTime::now()).map(|_| ())
+    }
+
+    #[test]
+    fn tofu_pins_the_first_certificate_and_refuses_a_changed_one() {
+        let dir = std::env::temp_dir().join(format!("p2p-tofu-{}", rand::random::<u64>()));
+        let known_hosts = dir.join("known_hosts");
+        let verifier = TofuServerVerification::new(known_hosts.clone(), HashSet::new());
+        let (first, second) = (self_signed(), self_signed());
+
+        verify_tofu(&verifier, "10.0.0.1", &first).unwrap();
+        verify_tofu(&verifier, "10.0.0.1", &first).unwrap();
+        assert!(verify_tofu(&verifier, "10.0.0.1", &second).is_err());
+        verify_tofu(&verifier, "10.0.0.2", &second).unwrap();
+
+        // Pins survive a restart and are shared by all verifiers on the file.
+        let restarted = TofuServerVerification::new(known_hosts.clone(), HashSet::new());
+        verify_tofu(&restarted, "10.0.0.1", &first).unwrap();
+        assert!(verify_tofu(&restarted, "10.0.0.1", &second).is_err());
+        assert_eq!(KnownHosts::load(&known_hosts).unwrap().get("10.0.0.1"), Some(fingerprint(&first).as_str()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tofu_accepts_a_changed_certificate_when_told_to() {
+        let dir = std::env::temp_dir().join(format!("p2p-tofu-{}", rand::random::<u64>()));
+        let known_hosts = dir.join("known_hosts");
+        let (first, second) = (self_signed(), self_signed());
+        verify_tofu(&TofuServerVerification::new(known_hosts.clone(), HashSet::new()), "10.0.0.1", &first).unwrap();
+
+        let accepting = TofuServerVerification::new(known_hosts.clone(), ["10.0.0.1".to_string()].into());
+        verify_tofu(&accepting, "10.0.0.1", &second).unwrap();
+
+        let strict = TofuServerVerification::new(known_hosts.clone(), HashSet::new());
+        verify_tofu(&strict, "10.0.0.1", &second).unwrap();
+        assert!(verify_tofu(&strict, "10.0.0.1", &first).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn known_hosts_round_trip() {
+        let parsed = KnownHosts::parse("# pinned servers\n10.0.0.1 sha256:ab\n\nnode2.local sha256:cd\n");
+        assert_eq!(parsed.get("10.0.0.1"), Some("sha256:ab"));
+        assert_eq!(parsed.get("node2.local"), Some("sha256:cd"));
+
+        let dir = std::env::temp_dir().join(format!("p2p-tofu-{}", rand::random::<u64>()));
+        parsed.save(&dir.join("known_hosts")).unwrap();
+        assert_eq!(KnownHosts::load(&dir.join("known_hosts")).unwrap(), parsed);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,467 @@
+//! In-memory LSB steganography over byte buffers, and the dispatch between it and the other
+//! embedding [`Mode`]s.
+//!
+//! The payload is prefixed with its length as a big-endian `u32` and written bit by bit, most
+//! significant bit first, into the least significant bit of the red, green and blue channels
+//! of the carrier, row by row. Alpha is left alone. Stego images are always PNG so the
+//! hidden bits survive.
+//!
+//! The length is written three times and read back by a majority vote on every bit, so that
+//! a single damaged bit in it does not lose a payload that error correction could repair.
+//!
+//! [`Mode::Scattered`] writes the same bits to channels in an order drawn from a ChaCha20
+//! stream seeded by an [`EmbedKey`]. The changes then spread over the whole image instead of
+//! filling it from the top, and without the key the bits cannot be put back in order.
+//!
+//! Nothing here touches the filesystem or shared state, so any number of calls may run at
+//! once on the RTO thread pool. Plain LSB embeds row by row and extracts in runs of bytes on
+//! the current rayon pool; the scattered order is drawn from one stream and stays sequential.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::dct::{self, DctParams};
+
+const LEN_BYTES: usize = 4;
+const HEADER_COPIES: usize = 3;
+const HEADER_BYTES: usize = LEN_BYTES * HEADER_COPIES;
+// Payload bytes read per task, so small payloads are not split up for nothing
+const MIN_BYTES_PER_TASK: usize = 4096;
+
+/// How a payload is laid out in the carrier.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Mode {
+    /// One bit in the least significant bit of every colour channel. Lossless PNG only.
+    #[default]
+    Lsb,
+    /// Bits in DCT coefficients of the luminance, see [`crate::dct`]. Survives JPEG.
+    Dct,
+    /// Like [`Mode::Lsb`], but in channels picked by an [`EmbedKey`].
+    Scattered,
+}
+
+impl Mode {
+    pub const ALL: &'static [Mode] = &[Mode::Lsb, Mode::Dct, Mode::Scattered];
+
+    /// Number of payload bytes a `width` x `height` carrier can hold in this mode.
+    pub fn capacity(self, width: u32, height: u32) -> usize {
+        match self {
+            Mode::Lsb | Mode::Scattered => capacity(width, height),
+            Mode::Dct => dct::capacity(width, height),
+        }
+    }
+}
+
+/// Number of payload bytes a `width` x `height` carrier can hold.
+pub fn capacity(width: u32, height: u32) -> usize {
+    (width as usize * height as usize * 3 / 8).saturating_sub(HEADER_BYTES)
+}
+
+/// `header` the number of times it is written in, back to back.
+pub(crate) fn repeat_header(header: &[u8]) -> Vec<u8> {
+    header.repeat(HEADER_COPIES)
+}
+
+/// Reads a header written by [`repeat_header`], taking every bit from the copies that agree.
+pub(crate) fn vote_header(copies: &[u8]) -> Vec<u8> {
+    let len = copies.len() / HEADER_COPIES;
+    (0..len)
+        .map(|i| {
+            let (a, b, c) = (copies[i], copies[len + i], copies[2 * len + i]);
+            (a & b) | (a & c) | (b & c)
+        })
+        .collect()
+}
+
+fn declared_len(copies: &[u8]) -> usize {
+    u32::from_be_bytes(vote_header(copies).try_into().unwrap()) as usize
+}
+
+/// Secret from which [`Mode::Scattered`] derives where the payload goes.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbedKey(pub String);
+
+impl fmt::Debug for EmbedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EmbedKey(..)")
+    }
+}
+
+impl EmbedKey {
+    fn rng(&self) -> ChaCha20Rng {
+        let mut hasher = Sha256::new();
+        hasher.update(b"p2p-scatter/1");
+        hasher.update(self.0.as_bytes());
+        ChaCha20Rng::from_seed(hasher.finalize().into())
+    }
+}
+
+// The data channels in an order only the key can reproduce: a Fisher-Yates shuffle of the
+// colour channels, drawn one position per bit. Only the positions the shuffle has swapped are
+// stored, so reading a short header costs a handful of entries rather than one per channel.
+struct Scattered {
+    // Number of colour channels, which are numbered 0.. skipping alpha
+    count: usize,
+    drawn: usize,
+    // Where a swap left a number other than its own
+    swapped: HashMap<usize, usize>,
+    rng: ChaCha20Rng,
+}
+
+impl Scattered {
+    fn new(len: usize, key: &EmbedKey) -> Self {
+        Self { count: len / 4 * 3, drawn: 0, swapped: HashMap::new(), rng: key.rng() }
+    }
+}
+
+impl Iterator for Scattered {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.drawn == self.count {
+            return None;
+        }
+        let pick = self.rng.gen_range(self.drawn..self.count);
+        let at_drawn = self.swapped.remove(&self.drawn).unwrap_or(self.drawn);
+        let channel = if pick == self.drawn {
+            at_drawn
+        } else {
+            self.swapped.insert(pick, at_drawn).unwrap_or(pick)
+        };
+        self.drawn += 1;
+        Some(channel / 3 * 4 + channel % 3)
+    }
+}
+
+/// Hides `payload` in a copy of `carrier`.
+pub fn embed(carrier: &DynamicImage, payload: &[u8]) -> Result<RgbaImage, String> {
+    let mut image = fitting(carrier, payload)?;
+    let header = repeat_header(&(payload.len() as u32).to_be_bytes());
+    let bits = (HEADER_BYTES + payload.len()) * 8;
+    let bit = |k: usize| {
+        let byte = if k / 8 < HEADER_BYTES { header[k / 8] } else { payload[k / 8 - HEADER_BYTES] };
+        (byte >> (7 - k % 8)) & 1
+    };
+
+    // Every row holds three bits per pixel, so where a row's bits start is known up front
+    let row_len = (image.width() as usize * 4).max(4);
+    let channels: &mut [u8] = &mut image;
+    channels.par_chunks_mut(row_len).enumerate().for_each(|(row, channels)| {
+        let first = row * row_len / 4 * 3;
+        for (i, channel) in channels.iter_mut().enumerate().filter(|(i, _)| i % 4 != 3) {
+            let k = first + i / 4 * 3 + i % 4;
+            if k >= bits {
+                break;
+            }
+            *channel = (*channel & !1) | bit(k);
+        }
+    });
+    Ok(image)
+}
+
+/// [`embed`] the way it ran before it was spread over the rayon pool, one bit after the other
+/// on the calling thread. Kept as the baseline for benchmarks and tests.
+#[doc(hidden)]
+pub fn embed_sequential(carrier: &DynamicImage, payload: &[u8]) -> Result<RgbaImage, String> {
+    embed_at(carrier, payload, in_order)
+}
+
+// The colour channels from the top left, for the sequential baseline
+fn in_order(len: usize) -> impl Iterator<Item = usize> {
+    (0..len).filter(|i| i % 4 != 3)
+}
+
+/// Hides `payload` in a copy of `carrier` in [`Mode::Scattered`].
+pub fn embed_scattered(carrier: &DynamicImage, payload: &[u8], key: &EmbedKey) -> Result<RgbaImage, String> {
+    embed_at(carrier, payload, |len| Scattered::new(len, key))
+}
+
+fn embed_at<P, I>(carrier: &DynamicImage, payload: &[u8], positions: P) -> Result<RgbaImage, String>
+where
+    P: FnOnce(usize) -> I,
+    I: Iterator<Item = usize>,
+{
+    let mut image = fitting(carrier, payload)?;
+    let header = repeat_header(&(payload.len() as u32).to_be_bytes());
+    let bits = header.iter().chain(payload).flat_map(|b| (0..8).rev().map(move |i| (b >> i) & 1));
+    let channels: &mut [u8] = &mut image;
+    for (i, bit) in positions(channels.len()).zip(bits) {
+        channels[i] = (channels[i] & !1) | bit;
+    }
+    Ok(image)
+}
+
+// The carrier as RGBA, if `payload` fits into it
+fn fitting(carrier: &DynamicImage, payload: &[u8]) -> Result<RgbaImage, String> {
+    let image = carrier.to_rgba();
+    let available = capacity(image.width(), image.height());
+    if payload.len() > available {
+        return Err(format!(
+            "payload of {} bytes does not fit into a {}x{} carrier, which holds {} bytes",
+            payload.len(), image.width(), image.height(), available
+        ));
+    }
+    Ok(image)
+}
+
+/// Reads back a payload written by [`embed`].
+pub fn extract(stego: &DynamicImage) -> Result<Vec<u8>, String> {
+    peek(stego, usize::MAX).map(|(_, payload)| payload)
+}
+
+/// [`extract`] on the calling thread alone, the baseline to [`embed_sequential`].
+#[doc(hidden)]
+pub fn extract_sequential(stego: &DynamicImage) -> Result<Vec<u8>, String> {
+    extract_at(stego, in_order)
+}
+
+/// Reads back a payload written by [`embed_scattered`] with the same key.
+pub fn extract_scattered(stego: &DynamicImage, key: &EmbedKey) -> Result<Vec<u8>, String> {
+    extract_at(stego, |len| Scattered::new(len, key))
+}
+
+/// Reads the declared length of a payload written by [`embed`] and at most `limit` of its
+/// leading bytes, leaving the rest of the image unread.
+pub fn peek(stego: &DynamicImage, limit: usize) -> Result<(usize, Vec<u8>), String> {
+    let image = stego.to_rgba();
+    let channels: &[u8] = &image;
+    // Bit k is in colour channel k % 3 of pixel k / 3
+    let byte = |n: usize| (n * 8..n * 8 + 8).fold(0u8, |byte, k| byte << 1 | channels[k / 3 * 4 + k % 3] & 1);
+
+    if channels.len() / 4 * 3 < HEADER_BYTES * 8 {
+        return Err("image is too small to hold a payload".to_string());
+    }
+    let len = declared_len(&(0..HEADER_BYTES).map(byte).collect::<Vec<u8>>());
+    if len > capacity(image.width(), image.height()) {
+        return Err("image does not contain a payload".to_string());
+    }
+    let bytes = HEADER_BYTES..HEADER_BYTES + len.min(limit);
+    Ok((len, bytes.into_par_iter().with_min_len(MIN_BYTES_PER_TASK).map(byte).collect()))
+}
+
+/// [`peek`] for a payload written by [`embed_scattered`].
+pub fn peek_scattered(stego: &DynamicImage, key: &EmbedKey, limit: usize) -> Result<(usize, Vec<u8>), String> {
+    read_at(stego, |len| Scattered::new(len, key), limit)
+}
+
+fn extract_at<P, I>(stego: &DynamicImage, positions: P) -> Result<Vec<u8>, String>
+where
+    P: FnOnce(usize) -> I,
+    I: Iterator<Item = usize>,
+{
+    read_at(stego, positions, usize::MAX).map(|(_, payload)| payload)
+}
+
+// The declared payload length, and the payload up to `limit` bytes
+fn read_at<P, I>(stego: &DynamicImage, positions: P, limit: usize) -> Result<(usize, Vec<u8>), String>
+where
+    P: FnOnce(usize) -> I,
+    I: Iterator<Item = usize>,
+{
+    let image = stego.to_rgba();
+    let available = capacity(image.width(), image.height());
+    let channels: &[u8] = &image;
+    let mut bits = positions(channels.len()).map(|i| channels[i] & 1);
+    let mut next_byte = || (0..8).try_fold(0u8, |byte, _| bits.next().map(|bit| byte << 1 | bit));
+
+    let mut header = [0u8; HEADER_BYTES];
+    for b in header.iter_mut() {
+        *b = next_byte().ok_or("image is too small to hold a payload")?;
+    }
+    let len = declared_len(&header);
+    if len > available {
+        return Err("image does not contain a payload".to_string());
+    }
+    let payload = (0..len.min(limit)).map(|_| next_byte().ok_or_else(|| "payload is truncated".to_string()));
+    Ok((len, payload.collect::<Result<_, _>>()?))
+}
+
+/// Hides `payload` in `carrier` and returns the stego image as PNG.
+pub fn hide(carrier: &DynamicImage, payload: &[u8]) -> Result<Vec<u8>, String> {
+    png(embed(carrier, payload)?)
+}
+
+fn png(stego: RgbaImage) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::new();
+    DynamicImage::ImageRgba8(stego)
+        .write_to(&mut buffer, ImageFormat::PNG)
+        .map_err(|e| e.to_string())?;
+    Ok(buffer)
+}
+
+/// Hides `payload` in `carrier` in [`Mode::Scattered`] and returns the stego image as PNG.
+pub fn hide_scattered(carrier: &DynamicImage, payload: &[u8], key: &EmbedKey) -> Result<Vec<u8>, String> {
+    png(embed_scattered(carrier, payload, key)?)
+}
+
+/// Reads the payload out of an encoded stego image.
+pub fn reveal(stego_image: &[u8]) -> Result<Vec<u8>, String> {
+    let stego = image::load_from_memory(stego_image).map_err(|e| format!("invalid stego image: {}", e))?;
+    extract(&stego)
+}
+
+/// Hides `payload` in `carrier` in `mode`: PNG for [`Mode::Lsb`], JPEG for [`Mode::Dct`].
+/// [`Mode::Scattered`] needs a key and goes through [`hide_scattered`].
+pub fn hide_with(carrier: &DynamicImage, payload: &[u8], mode: Mode, params: &DctParams) -> Result<Vec<u8>, String> {
+    match mode {
+        Mode::Lsb => hide(carrier, payload),
+        Mode::Dct => dct::hide(carrier, payload, params),
+        Mode::Scattered => Err("scattered mode needs an embedding key".to_string()),
+    }
+}
+
+/// Reads a payload hidden in any mode but [`Mode::Scattered`], which needs its key, returning
+/// the mode it was found in.
+pub fn extract_any(stego: &DynamicImage, params: &DctParams) -> Result<(Mode, Vec<u8>), String> {
+    match dct::extract(stego, params) {
+        Ok(payload) => Ok((Mode::Dct, payload)),
+        Err(_) => extract(stego).map(|payload| (Mode::Lsb, payload)),
+    }
+}
+
+/// [`reveal`] for stego images written in any mode.
+pub fn reveal_with(stego_image: &[u8], params: &DctParams) -> Result<Vec<u8>, String> {
+    let stego = image::load_from_memory(stego_image).map_err(|e| format!("invalid stego image: {}", e))?;
+    extract_any(&stego, params).map(|(_, payload)| payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn carrier(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(x * 7) as u8, (y * 13) as u8, (x ^ y) as u8, 200])
+        }))
+    }
+
+    #[test]
+    fn payload_round_trips_through_png() {
+        let payload: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let stego = hide(&carrier(64, 64), &payload).unwrap();
+        assert_eq!(reveal(&stego).unwrap(), payload);
+    }
+
+    #[test]
+    fn only_low_bits_of_colour_channels_change() {
+        let original = carrier(32, 32);
+        let stego = embed(&original, &[0xff; 300]).unwrap();
+        for (a, b) in original.to_rgba().pixels().zip(stego.pixels()) {
+            for c in 0..3 {
+                assert!((a[c] as i16 - b[c] as i16).abs() <= 1);
+            }
+            assert_eq!(a[3], b[3]);
+        }
+    }
+
+    #[test]
+    fn payload_must_fit() {
+        let available = capacity(16, 16);
+        assert!(embed(&carrier(16, 16), &vec![1; available]).is_ok());
+        assert!(embed(&carrier(16, 16), &vec![1; available + 1]).is_err());
+    }
+
+    #[test]
+    fn the_mode_is_detected_on_reveal() {
+        let params = DctParams { quality: 75, max_pixel_diff: 10 };
+        for mode in [Mode::Lsb, Mode::Dct] {
+            let stego = hide_with(&carrier(96, 96), b"payload", mode, &params).unwrap();
+            let image = image::load_from_memory(&stego).unwrap();
+            assert_eq!(extract_any(&image, &params).unwrap(), (mode, b"payload".to_vec()));
+        }
+    }
+
+    #[test]
+    fn scattered_payloads_spread_out_and_need_the_key() {
+        let key = EmbedKey("correct horse".to_string());
+        let original = carrier(64, 64);
+        let stego = embed_scattered(&original, b"short payload", &key).unwrap();
+        let stego = DynamicImage::ImageRgba8(stego);
+        assert_eq!(extract_scattered(&stego, &key).unwrap(), b"short payload");
+        assert_ne!(extract_scattered(&stego, &EmbedKey("wrong horse".to_string())).ok().as_deref(), Some(&b"short payload"[..]));
+        assert_ne!(extract(&stego).ok().as_deref(), Some(&b"short payload"[..]));
+
+        // Sequential embedding would only touch the first rows
+        let rows: Vec<u32> = original.to_rgba().enumerate_pixels().zip(stego.to_rgba().pixels())
+            .filter(|((_, _, a), b)| a != b)
+            .map(|((_, y, _), _)| y)
+            .collect();
+        assert!(rows.iter().any(|&y| y > 48) && rows.iter().any(|&y| y < 16));
+    }
+
+    #[test]
+    fn the_scattered_order_is_a_plain_shuffle_of_the_colour_channels() {
+        let key = EmbedKey("correct horse".to_string());
+        let mut rng = key.rng();
+        let mut channels: Vec<usize> = (0..400).filter(|i| i % 4 != 3).collect();
+        for drawn in 0..channels.len() {
+            let pick = rng.gen_range(drawn..channels.len());
+            channels.swap(drawn, pick);
+        }
+        assert_eq!(Scattered::new(400, &key).collect::<Vec<_>>(), channels);
+    }
+
+    #[test]
+    fn a_flipped_bit_in_the_length_is_outvoted() {
+        let key = EmbedKey("correct horse".to_string());
+        let mut plain = embed(&carrier(32, 32), b"payload").unwrap();
+        let mut scattered = embed_scattered(&carrier(32, 32), b"payload", &key).unwrap();
+        let order: Vec<usize> = Scattered::new(scattered.len(), &key).take(HEADER_BYTES * 8).collect();
+        // The last bit of the first copy and the first bit of the last one
+        for k in [31, 64] {
+            plain[(k as u32 / 3, 0)][k % 3] ^= 1;
+            let channels: &mut [u8] = &mut scattered;
+            channels[order[k]] ^= 1;
+        }
+        assert_eq!(extract(&DynamicImage::ImageRgba8(plain)).unwrap(), b"payload");
+        assert_eq!(extract_scattered(&DynamicImage::ImageRgba8(scattered), &key).unwrap(), b"payload");
+    }
+
+    #[test]
+    fn concurrent_calls_do_not_interfere() {
+        let handles: Vec<_> = (0..8u8)
+            .map(|n| std::thread::spawn(move || {
+                let payload = vec![n; 500 + n as usize];
+                let stego = hide(&carrier(48, 48), &payload).unwrap();
+                assert_eq!(reveal(&stego).unwrap(), payload);
+            }))
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn the_result_does_not_depend_on_the_thread_count() {
+        let params = DctParams { quality: 75, max_pixel_diff: u8::MAX };
+        let payload: Vec<u8> = (0..7000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let hide_both = || {
+            let lsb = embed(&carrier(160, 120), &payload).unwrap().into_raw();
+            let dct = dct::embed(&carrier(160, 120), &payload[..90], &params).unwrap().into_raw();
+            (lsb, dct)
+        };
+        let single = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+        let (lsb, dct) = single.install(hide_both);
+        assert_eq!((lsb.clone(), dct.clone()), hide_both());
+        let sequential = (
+            embed_sequential(&carrier(160, 120), &payload).unwrap().into_raw(),
+            dct::embed_sequential(&carrier(160, 120), &payload[..90], &params).unwrap().into_raw(),
+        );
+        assert_eq!((lsb.clone(), dct.clone()), sequential);
+
+        let stego = DynamicImage::ImageRgba8(RgbaImage::from_raw(160, 120, lsb).unwrap());
+        assert_eq!(single.install(|| extract(&stego)).unwrap(), payload);
+        assert_eq!(extract(&stego).unwrap(), payload);
+        assert_eq!(extract_sequential(&stego).unwrap(), payload);
+        let stego = DynamicImage::ImageRgba8(RgbaImage::from_raw(160, 120, dct).unwrap());
+        assert_eq!(dct::extract(&stego, &params).unwrap(), dct::extract_sequential(&stego, &params).unwrap());
+    }
+}
@@ -1,17 +1,10 @@
-use crossbeam::channel::{bounded, Receiver, Select, SelectTimeoutError, Sender};
 use remote_trait_object::transport::*;
-use log::debug;
-use quinn::{Endpoint, ClientConfig, ServerConfig, Connection, SendStream, RecvStream};
-use futures::executor;
-use std::net::{SocketAddr, UdpSocket};
-use std::sync::Arc;
-use std::error::Error;
+use quinn::{Connection, Endpoint};
+use std::net::SocketAddr;
 use std::thread;
 use tokio::runtime::Runtime;
 use std::hash::{Hash, Hasher};
-use std::net::IpAddr;
 use crate::quinn_utils::*;
-use local_ip_address::local_ip;
 
 // Custom transport error types
 #[derive(Debug)]
@@ -32,7 +25,6 @@ impl From<quinn::ConnectionError> for QuinnTransportError {
 #[derive(Debug,Clone)]
 pub struct QuinnSend {
     connection: Connection,
-    //runtime: Arc<Runtime>,
 }
 
 
@@ -40,25 +32,8 @@ impl TransportSend for QuinnSend {
     fn send(
         &self,
         data: &[u8],
-        timeout: Option<std::time::Duration>,
+        _timeout: Option<std::time::Duration>,
     ) -> Result<(), TransportError> {
-        let data = data.to_vec();
-        /*
-        let connection = self.connection.clone();
-        let result = thread::spawn(move || {
-            futures::executor::block_on(async {
-                let (mut send, _recv) = connection.open_bi().await
-                    .map_err(|_| TransportError::Custom)?;
-                
-                send.write_all(&data).await
-                    .map_err(|_| TransportError::Custom)?;
-                
-                send.finish()
-                    .map_err(|_| TransportError::Custom)
-            })
-        }).join().expect("Thread panicked");
-        */
-        
         let data = data.to_vec();
         let connection = self.connection.clone();
         let result = thread::spawn(move || {
@@ -66,13 +41,13 @@ impl TransportSend for QuinnSend {
             rt.block_on(async move {
                 match connection.open_bi().await {
                     Ok((mut send, _recv)) => {
-                
+
                         send.write_all(&data).await
                             .map_err(|e| {
                                 eprintln!("Error writing data: {:?}", e);
                                 TransportError::Custom
                             })?;
-                        
+
                         send.finish()
                             .map_err(|e| {
                                 eprintln!("Error finishing stream: {:?}", e);
@@ -85,7 +60,7 @@ impl TransportSend for QuinnSend {
                     }
                 }
             })
-        }).join().expect("Thread panicked");
+        }).join().map_err(|_| TransportError::Custom)?;
 
         result
     }
@@ -99,40 +74,19 @@ impl TransportSend for QuinnSend {
 #[derive(Debug, Clone)]
 pub struct QuinnRecv {
     connection: Connection,
-    //runtime: Arc<Runtime>,
 }
 
 impl TransportRecv for QuinnRecv {
-    fn recv(&self, timeout: Option<std::time::Duration>) -> Result<Vec<u8>, TransportError> {
-        
-        /*
-        let connection = self.connection.clone();
-        
-        let result:Result<Vec<u8>, TransportError> = thread::spawn(move || {
-            futures::executor::block_on(async {
-                let (_, mut recv) = connection.accept_bi().await
-                    .map_err(|_| TransportError::Custom)?;
-                
-                let mut buffer = Vec::new();
-                let max_size = 30*1024 * 1024; // 30MB max size, adjust as needed
-                buffer = recv.read_to_end(max_size).await
-                    .map_err(|_| TransportError::Custom)?;
-                
-                Ok(buffer)
-            })
-        }).join().expect("Thread panicked");
-        */
-
+    fn recv(&self, _timeout: Option<std::time::Duration>) -> Result<Vec<u8>, TransportError> {
         let connection = self.connection.clone();
         let result = thread::spawn(move || {
             let rt = Runtime::new().expect("Failed to create runtime");
             rt.block_on(async move {
                 match connection.accept_bi().await {
                     Ok((_, mut recv)) => {
-                
-                        let mut buffer = Vec::new();
+
                         let max_size = 500 * 1024 * 1024; // 500MB max size, adjust as needed
-                        buffer = recv.read_to_end(max_size).await
+                        let buffer = recv.read_to_end(max_size).await
                             .map_err(|e| {
                                 eprintln!("Error reading data: {:?}", e);
                                 TransportError::Custom
@@ -142,11 +96,13 @@ impl TransportRecv for QuinnRecv {
                     },
                     Err(e) => {
                         eprintln!("Error accepting stream: {:?}", e);
+                        // Close the connection and drop the used port
+                        connection.close(0u32.into(), b"connection error");
                         Err(TransportError::Custom)
                     }
                 }
             })
-        }).join().expect("Thread panicked");
+        }).join().map_err(|_| TransportError::Custom)?;
 
         result
     }
@@ -198,10 +154,7 @@ impl TransportEnds {
             tokio::time::timeout(std::time::Duration::from_secs(5), self.send.connection.open_uni()).await
         });
 
-        match result {
-            Ok(Ok(_)) => true,
-            _ => false,
-        }
+        matches!(result, Ok(Ok(_)))
 
     }
     pub fn get_connection_id(&self) -> String{
@@ -210,66 +163,51 @@ impl TransportEnds {
     pub fn get_remote_address(&self) -> String{
         format!("{}", self.send.connection.remote_address())
     }
+    /// User name from the client certificate of the remote end, see [`peer_identity`].
+    pub fn peer_identity(&self) -> Option<String> {
+        peer_identity(&self.send.connection)
+    }
+    pub fn close(&self, reason: &str) {
+        self.send.connection.close(0u32.into(), reason.as_bytes());
+    }
 
 
 }
 
 
-// Create function now establishes Quinn connections
-pub async fn create(server_conn: Connection) -> Result<TransportEnds, String> {
-    
-    // Establish connections
-    println!("Establishing connections...");
-    //let server_conn = server_endpoint.accept().await.unwrap().await.map_err(|e| e.to_string())?;
-    let local_ip: IpAddr = local_ip().unwrap();
-    let local_addr = SocketAddr::new(local_ip, 0);
-
-    let socket = UdpSocket::bind(local_addr).map_err(|e| e.to_string())?;
-    let actual_addr = socket.local_addr().map_err(|e| e.to_string())?;
-    println!("Assigned port: {}", actual_addr.port());
-    std::mem::drop(socket);
-
-    let addr = format!("{}", actual_addr);
-    println!("Sending address: {}", addr);
-    match server_conn.open_bi().await {
-        Ok((mut send, _recv)) => {
-    
-            send.write_all(addr.as_bytes()).await
-                .map_err(|e| {
-                    eprintln!("Error writing data: {:?}", e);
-                    e.to_string()
-                })?;
-            
-            send.finish()
-                .map_err(|e| {
-                    eprintln!("Error finishing stream: {:?}", e);
-                    e.to_string()
-                })?;
-        },
-        Err(e) => {
-            eprintln!("Error opening stream: {:?}", e);
-            return Err(e.to_string());
-        }
-    };
+// Client side: dial the steganography service. The endpoint's client config has to ask for
+// `mux::ALPN_STEG`, which is what routes the connection to the service on the server.
+pub async fn connect(client_endpoint: Endpoint, server_address: SocketAddr) -> Result<TransportEnds, String> {
 
-    let (endpoint, _cert) = make_server_endpoint(actual_addr).unwrap();
-    let new_conn = endpoint.accept().await.unwrap().await.map_err(|e| e.to_string())?;
+    println!("Establishing connection...");
+    let client_conn = client_endpoint.connect(
+        server_address,
+        &server_name(&server_address),
+    ).map_err(|e| e.to_string())?
+    .await.map_err(|e| e.to_string())?;
 
-    println!("Connections established successfully.");
+    println!("Connection established successfully.");
 
-    let transport_ends = TransportEnds {
+    Ok(TransportEnds {
         send: QuinnSend {
-            connection: new_conn.clone(),
+            connection: client_conn.clone(),
         },
         recv: QuinnRecv {
-            connection: new_conn.clone(),
+            connection: client_conn,
         },
-    };
-
-    // Close the conections
-    server_conn.close(0u32.into(), b"endpoint closed");
-
-
-    Ok(transport_ends)
+    })
+}
 
-}
\ No newline at end of file
+// Server side: wrap a connection the router handed to the steganography service. The
+// service runs on the connection the client opened, so the node needs no ports besides the
+// shared one.
+pub async fn accept(server_conn: Connection) -> Result<TransportEnds, String> {
+    Ok(TransportEnds {
+        send: QuinnSend {
+            connection: server_conn.clone(),
+        },
+        recv: QuinnRecv {
+            connection: server_conn,
+        },
+    })
+}
@@ -0,0 +1,214 @@
+//! Client identities and what they are allowed to do.
+//!
+//! Clients authenticate with a certificate issued by the cluster CA; the common name of that
+//! certificate is the user name. The service provider looks the name up in a users file and
+//! every service object it hands out is bound to the resulting [`Session`].
+//!
+//! ```toml
+//! [users.alice]
+//! permissions = ["encode", "decode"]
+//! max_requests = 1000
+//! max_bytes = 104857600
+//! ```
+//!
+//! Quota usage is kept in memory and shared by all connections of a user; it starts from zero
+//! when the node restarts.
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use serde::Deserialize;
+
+use crate::quinn_utils::TlsConfig;
+
+/// User name given to connections that did not present a client certificate, which only
+/// happens outside of [`TlsConfig::Cluster`].
+pub const ANONYMOUS: &str = "anonymous";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Encode,
+    Decode,
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permission::Encode => write!(f, "encode"),
+            Permission::Decode => write!(f, "decode"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct UserRecord {
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+    /// Maximum number of calls, `None` for unlimited.
+    pub max_requests: Option<u64>,
+    /// Maximum number of payload bytes sent to the service in total, `None` for unlimited.
+    pub max_bytes: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub requests: u64,
+    pub bytes: u64,
+}
+
+#[derive(Deserialize)]
+struct UsersFile {
+    #[serde(default)]
+    users: HashMap<String, UserRecord>,
+}
+
+/// The user records of a node together with the quota usage of each user.
+#[derive(Debug, Default)]
+pub struct UserDirectory {
+    users: HashMap<String, UserRecord>,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl UserDirectory {
+    pub fn new(users: HashMap<String, UserRecord>) -> Self {
+        Self { users, usage: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let file: UsersFile = toml::from_str(text)?;
+        Ok(Self::new(file.users))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+
+    /// Reads the users file named by `P2P_USERS_FILE` (default `users.toml`).
+    ///
+    /// Without a cluster CA nobody presents a certificate, so a missing file there lets
+    /// [`ANONYMOUS`] do everything instead of failing.
+    pub fn from_env(tls: &TlsConfig) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let path = env::var("P2P_USERS_FILE").unwrap_or_else(|_| "users.toml".to_string());
+        let path = Path::new(&path);
+        if !matches!(tls, TlsConfig::Cluster { .. }) && !path.exists() {
+            let mut users = HashMap::new();
+            users.insert(
+                ANONYMOUS.to_string(),
+                UserRecord { permissions: vec![Permission::Encode, Permission::Decode], ..Default::default() },
+            );
+            return Ok(Self::new(users));
+        }
+        Self::load(path)
+    }
+
+    /// Binds a connection to its user record. `identity` is the name from the client
+    /// certificate, `None` if the client did not present one.
+    pub fn session(self: &Arc<Self>, identity: Option<String>) -> Result<Session, String> {
+        let user = identity.unwrap_or_else(|| ANONYMOUS.to_string());
+        if !self.users.contains_key(&user) {
+            return Err(format!("unknown user {:?}", user));
+        }
+        Ok(Session { user, directory: self.clone() })
+    }
+
+    pub fn usage(&self, user: &str) -> Usage {
+        self.usage.lock().unwrap().get(user).copied().unwrap_or_default()
+    }
+
+    fn permit(&self, user: &str, permission: Permission) -> Result<&UserRecord, String> {
+        let record = self.users.get(user).ok_or_else(|| format!("unknown user {:?}", user))?;
+        if !record.permissions.contains(&permission) {
+            return Err(format!("user {:?} is not allowed to {}", user, permission));
+        }
+        Ok(record)
+    }
+
+    fn authorize(&self, user: &str, permission: Permission, bytes: usize) -> Result<(), String> {
+        let record = self.permit(user, permission)?;
+
+        let mut usage = self.usage.lock().unwrap();
+        let used = usage.entry(user.to_string()).or_default();
+        let bytes = bytes as u64;
+        if record.max_requests.is_some_and(|max| used.requests >= max) {
+            return Err(format!("user {:?} has used up its request quota", user));
+        }
+        if record.max_bytes.is_some_and(|max| used.bytes + bytes > max) {
+            return Err(format!("user {:?} has used up its byte quota", user));
+        }
+        used.requests += 1;
+        used.bytes += bytes;
+        Ok(())
+    }
+}
+
+/// The user a service object acts for.
+#[derive(Clone, Debug)]
+pub struct Session {
+    user: String,
+    directory: Arc<UserDirectory>,
+}
+
+impl Session {
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Checks that the user may perform `permission` at all, without charging anything.
+    pub fn permit(&self, permission: Permission) -> Result<(), String> {
+        self.directory.permit(&self.user, permission).map(|_| ())
+    }
+
+    /// Checks that the user may perform `permission` on a payload of `bytes` bytes and charges
+    /// the call against its quota.
+    pub fn authorize(&self, permission: Permission, bytes: usize) -> Result<(), String> {
+        self.directory.authorize(&self.user, permission, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USERS: &str = r#"
+        [users.alice]
+        permissions = ["encode", "decode"]
+        max_requests = 2
+
+        [users.bob]
+        permissions = ["decode"]
+        max_bytes = 100
+    "#;
+
+    #[test]
+    fn permissions_are_enforced() {
+        let directory = Arc::new(UserDirectory::parse(USERS).unwrap());
+        let bob = directory.session(Some("bob".to_string())).unwrap();
+        assert!(bob.authorize(Permission::Decode, 10).is_ok());
+        assert!(bob.authorize(Permission::Encode, 10).is_err());
+        assert!(directory.session(Some("mallory".to_string())).is_err());
+        assert!(directory.session(None).is_err());
+    }
+
+    #[test]
+    fn quotas_are_shared_by_sessions_of_a_user() {
+        let directory = Arc::new(UserDirectory::parse(USERS).unwrap());
+        let first = directory.session(Some("alice".to_string())).unwrap();
+        let second = directory.session(Some("alice".to_string())).unwrap();
+        assert!(first.authorize(Permission::Encode, 1).is_ok());
+        assert!(second.authorize(Permission::Decode, 1).is_ok());
+        assert!(first.authorize(Permission::Encode, 1).is_err());
+        assert_eq!(directory.usage("alice"), Usage { requests: 2, bytes: 2 });
+
+        let bob = directory.session(Some("bob".to_string())).unwrap();
+        assert!(bob.authorize(Permission::Decode, 60).is_ok());
+        assert!(bob.authorize(Permission::Decode, 60).is_err());
+        assert!(bob.authorize(Permission::Decode, 40).is_ok());
+    }
+}
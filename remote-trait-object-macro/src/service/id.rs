@@ -45,9 +45,9 @@ pub(super) fn generate_id(
         let lit_index = lit_index(i);
         let lit_method_name = syn::LitStr::new(&format!("{}", method.sig.ident), Span::call_site());
 
-        let id_ident = id_method_ident(&source_trait, method);
-        let id_entry_ident = id_method_entry_ident(&source_trait, method);
-        let id_setter_ident = id_method_setter_ident(&source_trait, method);
+        let id_ident = id_method_ident(source_trait, method);
+        let id_entry_ident = id_method_entry_ident(source_trait, method);
+        let id_setter_ident = id_method_setter_ident(source_trait, method);
         let id_entry = quote! {
             #[allow(non_upper_case_globals)]
             static #id_ident: #env_path::MethodIdAtomic = #env_path::MethodIdAtomic::new(#lit_index);
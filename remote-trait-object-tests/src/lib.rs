@@ -3,6 +3,7 @@ extern crate log;
 
 #[cfg(test)]
 mod ping;
+#[cfg(test)]
 mod simple;
 mod test_store;
 pub mod transport;
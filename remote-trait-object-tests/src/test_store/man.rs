@@ -42,6 +42,31 @@ fn test_runner(f: impl Fn(Box<dyn Store>)) {
     store_runner.join().unwrap();
 }
 
+pub fn massive_no_export(n: usize) {
+    fn f(n: usize, store: Box<dyn Store>) {
+        for _ in 0..n {
+            assert_eq!(
+                store.order_pizza(Pizza::Pepperoni, 13),
+                "Here's a delicious pepperoni pizza"
+            );
+        }
+    }
+    test_runner(|store: Box<dyn Store>| f(n, store));
+}
+
+pub fn massive_with_export(n: usize) {
+    fn f(n: usize, store: Box<dyn Store>) {
+        for _ in 0..n {
+            let card = Box::new(MyCreditCard { balance: 13 }) as Box<dyn CreditCard>;
+            assert_eq!(
+                store.order_pizza_credit_card(Pizza::Pepperoni, ServiceRef::create_export(card)),
+                "Here's a delicious pepperoni pizza"
+            );
+        }
+    }
+    test_runner(|store: Box<dyn Store>| f(n, store));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,28 +240,3 @@ mod tests {
         );
     }
 }
-
-pub fn massive_no_export(n: usize) {
-    fn f(n: usize, store: Box<dyn Store>) {
-        for _ in 0..n {
-            assert_eq!(
-                store.order_pizza(Pizza::Pepperoni, 13),
-                "Here's a delicious pepperoni pizza"
-            );
-        }
-    }
-    test_runner(|store: Box<dyn Store>| f(n, store));
-}
-
-pub fn massive_with_export(n: usize) {
-    fn f(n: usize, store: Box<dyn Store>) {
-        for _ in 0..n {
-            let card = Box::new(MyCreditCard { balance: 13 }) as Box<dyn CreditCard>;
-            assert_eq!(
-                store.order_pizza_credit_card(Pizza::Pepperoni, ServiceRef::create_export(card)),
-                "Here's a delicious pepperoni pizza"
-            );
-        }
-    }
-    test_runner(|store: Box<dyn Store>| f(n, store));
-}
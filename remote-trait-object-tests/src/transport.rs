@@ -67,7 +67,7 @@ impl TransportRecv for IntraRecv {
                 }
             },
             i if i == terminator_index => {
-                let _ = selected_op
+                selected_op
                     .recv(&self.terminator_receiver)
                     .expect("Terminator should be dropped after this thread");
                 return Err(TransportError::Termination);
@@ -288,6 +288,7 @@ impl Context {
     /// Use this if you have nothing to do while the connection is working well.
     ///
     /// TODO: We should actually consider `timeout`
+    #[allow(clippy::result_large_err)]
     pub fn wait(mut self, timeout: Option<std::time::Duration>) -> Result<(), Self> {
         if let Err(multiplexer) = self
             .multiplexer
@@ -9,10 +9,10 @@ use std::fmt;
 use std::sync::{Arc, Weak};
 
 pub type ServiceObjectId = u32;
-pub const DELETE_REQUEST: crate::service::MethodId = std::u32::MAX;
+pub const DELETE_REQUEST: crate::service::MethodId = u32::MAX;
 pub const META_SERVICE_OBJECT_ID: ServiceObjectId = 0;
 pub const INITIAL_SERVICE_OBJECT_ID: ServiceObjectId = 1;
-pub const NULL_ID: ServiceObjectId = std::u32::MAX;
+pub const NULL_ID: ServiceObjectId = u32::MAX;
 
 pub struct ServiceForwarder {
     service_objects: RwLock<HashMap<ServiceObjectId, Arc<dyn Dispatch>>>,
@@ -91,8 +91,7 @@ impl ServiceForwarder {
                     .get(&object_id)
                     .unwrap_or_else(|| panic!("Fail to find {} from ServiceForwarder", object_id)),
             );
-            let result = handler.dispatch_and_call(method, data);
-            result
+            handler.dispatch_and_call(method, data)
         }
     }
 
@@ -5,7 +5,7 @@ based on trait objects.
 It is...
 
 1. Based on _services_ that can be exported and imported **as trait objects** -
-You register a service object, which is a trait object, and export it. On the other side, you import it into a proxy object, which is also a trait object.
+   You register a service object, which is a trait object, and export it. On the other side, you import it into a proxy object, which is also a trait object.
 1. Based on a point-to-point connection - All operations are conducted upon a single connection, which has **two ends**.
 1. Easy to export and import services - During a remote method call in some service, you **can export and import another service as an argument or a return value** of the method.
 1. Independent from the transport model - The transport model is abstracted and **users must provide a concrete implementation of it**.
@@ -71,7 +71,7 @@ It is sometimes called an _exchange_ when referring to both _export_ and _import
 
 1. User calls a method of _proxy object_ which is a trait object wrapped in a smart pointer.
 2. The call will be delivered to the _context_ from which the _proxy object_ is imported, after serialized into a byte packet.
-Note that the actual transportation of data happens only at the _context_, which functions as a connection end.
+   Note that the actual transportation of data happens only at the _context_, which functions as a connection end.
 3. The packet will be sent to the other end, (or context) by the _transport_.
 4. After the other side's _context_ receives the packet, it forwards the packet to the target _skeleton_ in its registry.
 5. The skeleton will dispatch the packet into an actual method call to the _service object_, which is a trait object wrapped in a smart pointer.
@@ -95,13 +95,13 @@ Both can decide which to use, depending on their own requirements.
 
 **Exporter (server)**
 - Use `Box<>` when you have nothing to do with the object after you export it.
-It will be registered in the [`Context`], and will be alive until the corresponding proxy object is dropped.
-You can never access the object directly, since it will be _moved_ to the registry.
+  It will be registered in the [`Context`], and will be alive until the corresponding proxy object is dropped.
+  You can never access the object directly, since it will be _moved_ to the registry.
 
 - Use `Arc<>` when you have something to do with the object after you export it, by `Arc::clone()` and holding somewhere.
-In this case, both its proxy object and some `Arc` copy on the exporter side can access the object,
-though the latter can only access it immutably.
-With this a single _service object_ can be shared among multiple _skeletons_ while a _skeleton_ always matches to exactly one _service object_.
+  In this case, both its proxy object and some `Arc` copy on the exporter side can access the object,
+  though the latter can only access it immutably.
+  With this a single _service object_ can be shared among multiple _skeletons_ while a _skeleton_ always matches to exactly one _service object_.
 
 - Use `Arc<RwLock<>>` when you have to access the object **mutably**, in the similar situation with `Arc` case.
 
@@ -127,15 +127,15 @@ There are some rules to use a trait as a service trait.
 1. No generic parameter (including lifetime) is allowed, in both trait definition and methods.
 
 1. All types appeared in method parameter or return value must implement [`serde`]'s [`Serialize`] and [`Deserialize`].
-This library performs de/serialization of data using [`serde`], though the data format can be chosen.
-Depending on your choice of macro arguments, this condition may differ slightly. See this [section](https://github.com/CodeChain-io/remote-trait-object)
+   This library performs de/serialization of data using [`serde`], though the data format can be chosen.
+   Depending on your choice of macro arguments, this condition may differ slightly. See this [section](https://github.com/CodeChain-io/remote-trait-object)
 
 1. You can't return a reference as a return type.
-This holds for a composite type too. For example, you can't return `&i32` nor `(i32, i32, &i32)`.
+   This holds for a composite type too. For example, you can't return `&i32` nor `(i32, i32, &i32)`.
 
 1. You can pass only first-order reference as a parameter.
-For example, you can pass `&T` only if the `T` doesn't a contain reference at all.
-Note that T must be `Sized`. There are two exceptions that accept `?Sized` `T`s: `str` and `[U]` where `U` doesn't contain reference at all.
+   For example, you can pass `&T` only if the `T` doesn't a contain reference at all.
+   Note that T must be `Sized`. There are two exceptions that accept `?Sized` `T`s: `str` and `[U]` where `U` doesn't contain reference at all.
 
 ### Example
 ```
@@ -198,7 +198,7 @@ impl Packet {
         &self.buffer
     }
 
-    pub fn view(&self) -> PacketView {
+    pub fn view(&self) -> PacketView<'_> {
         PacketView::new(&self.buffer)
     }
 
@@ -36,6 +36,7 @@ pub struct IdMap {
 /// # Examples
 /// ```
 /// use remote_trait_object::macro_env::*;
+/// use std::collections::HashMap;
 /// #[allow(non_upper_case_globals)]
 /// static ID_METHOD_MyTrait_mymethod: MethodIdAtomic = MethodIdAtomic::new(1);
 /// #[linkme::distributed_slice(MID_REG)]
@@ -46,16 +47,13 @@ pub struct IdMap {
 /// fn id_method_setter_MyTrait_mymethod(id: MethodId) {
 ///     ID_METHOD_MyTrait_mymethod.store(id, ID_ORDERING);
 /// }
-/// #[test]
-/// fn setup() {
-///     let id_map: HashMap<(String, String), MethodId> =
-///         [(("MyTrait".to_owned(), "mymethod".to_owned()), 123)].iter().cloned().collect();
-///     let id_map = IdMap {
-///         method_map: Some(id_map),
-///     };
-///     setup_identifiers(&id_map);
-///     assert_eq!(ID_METHOD_MyTrait_mymethod.load(ID_ORDERING), 123);
-/// }
+/// let id_map: HashMap<(String, String), MethodId> =
+///     [(("MyTrait".to_owned(), "mymethod".to_owned()), 123)].iter().cloned().collect();
+/// let id_map = IdMap {
+///     method_map: Some(id_map),
+/// };
+/// setup_identifiers(&id_map);
+/// assert_eq!(ID_METHOD_MyTrait_mymethod.load(ID_ORDERING), 123);
 /// ```
 pub fn setup_identifiers(descriptor: &IdMap) {
     // distributed_slices integrity test
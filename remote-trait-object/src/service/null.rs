@@ -73,6 +73,8 @@ impl crate::macro_env::IntoSkeleton<dyn NullService>
 }
 #[derive(Debug)]
 pub struct NullServiceProxy {
+    // Never called through, only held so that dropping it releases the remote object
+    #[allow(dead_code)]
     handle: crate::macro_env::Handle,
 }
 impl NullService for NullServiceProxy {}
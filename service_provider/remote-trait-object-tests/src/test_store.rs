@@ -1,5 +0,0 @@
-mod man;
-mod store;
-mod types;
-
-pub use man::{massive_no_export, massive_with_export};
@@ -1,11 +1,9 @@
-use rustls::pki_types::CertificateDer;
 use tokio::sync::mpsc;
 use tokio::time::{Duration, sleep, timeout};
 use std::collections::HashMap;
 use rand::Rng;
 use std::time::Instant;
 use std::cmp::Ordering;
-use serde::{Serialize, Deserialize};
 use quinn::{ClientConfig, Connection, Endpoint, ServerConfig, TransportConfig};
 use quinn_proto::crypto::rustls::QuicClientConfig;
 use std::net::SocketAddr;
@@ -13,7 +11,10 @@ use std::error::Error;
 use anyhow::Result;
 use std::sync::Arc;
 use sysinfo::{System};
-use crate::{quinn_utils::*, CURRENT_LEADER_ID};
+use common::quinn_utils::*;
+use common::mux::ALPN_ELECTION;
+use common::election::{Candidate, NodeMessage, SystemMetrics, VoteReason};
+use crate::CURRENT_LEADER_ID;
 use std::future::Future;
 use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 use std::fs::File;
@@ -27,46 +28,6 @@ pub enum State {
     DefactoLeader, // Temporary state when handling election after leader death
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum VoteReason {
-    HighCPULoad,
-    HighMemoryUsage,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SystemMetrics {
-    pub cpu_load: f64,
-    pub memory_usage: f64,
-    pub load_average: f64,
-}
-
-impl Default for SystemMetrics {
-    fn default() -> Self {
-        Self {
-            cpu_load: 50.0,
-            memory_usage: 60.0,
-            load_average: 70.0,
-            
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum NodeMessage {
-    Heartbeat { 
-        leader_id: u64, 
-        metrics: SystemMetrics,
-        candidates: Vec<Candidate>,
-    },
-    NegativeVote { 
-        voter_id: u64, 
-        reason: VoteReason,
-        metrics: SystemMetrics,
-    },
-    ElectionResult { new_leader_id: u64 },
-    UpdateMetrics(SystemMetrics),
-}
-
 pub struct Node {
     pub id: u64,
     pub state: State,
@@ -76,19 +37,18 @@ pub struct Node {
     negative_votes_received: HashMap<u64, VoteReason>,
     candidates: Vec<Candidate>,
     current_leader_id: Option<u64>,
-    pub server_endpoint: Endpoint,
-    pub _cert: CertificateDer<'static>, 
+    /// Election connections from peers, routed here from the node's shared endpoint.
+    incoming: mpsc::Receiver<Connection>,
     pub client_endpoints: Vec<(SocketAddr, Endpoint)>,
 }
 
 impl Node {
     pub async fn new(
         id: u64,
-        server_addr: SocketAddr,
+        incoming: mpsc::Receiver<Connection>,
         peer_addrs: Vec<SocketAddr>,
+        tls: &TlsConfig,
     ) -> Result<Self> {
-       // println!("Setting up server endpoint on {}", server_addr);
-        let (server_endpoint, _cert) = make_server_endpoint(server_addr).map_err(|e| anyhow::anyhow!(e))?;
         
        //println!("Setting up client endpoints for {} peers", peer_addrs.len());
         let mut client_endpoints = Vec::new();
@@ -97,12 +57,7 @@ impl Node {
             let mut client_endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap())?;
             //println!("Client endpoint created for peer {}", peer_addr);
             
-            let mut client_config = ClientConfig::new(Arc::new(QuicClientConfig::try_from(
-                rustls::ClientConfig::builder()
-                    .dangerous()
-                    .with_custom_certificate_verifier(SkipServerVerification::new())
-                    .with_no_client_auth(),
-            )?));
+            let mut client_config = configure_client_tls(tls, ALPN_ELECTION).map_err(|e| anyhow::anyhow!(e))?;
             
             let mut transport_config = TransportConfig::default();
             transport_config.keep_alive_interval(Some(Duration::from_secs(10)));
@@ -121,8 +76,7 @@ impl Node {
             negative_votes_received: HashMap::new(),
             candidates: Vec::new(),
             current_leader_id: None,
-            server_endpoint,
-            _cert,
+            incoming,
             client_endpoints,
         };
         node.metrics = node.collect_metrics();
@@ -159,31 +113,29 @@ impl Node {
             self.metrics = new_metrics;
             self.broadcast_heartbeat().await;
             
-            match timeout(Duration::from_secs(1), self.server_endpoint.accept()).await {
-                Ok(Some(incoming)) => {
-                    if let Ok(conn) = incoming.await {
-                        if let Ok((send, mut recv)) = conn.accept_bi().await {
-                            if let Ok(msg_bytes) = recv.read_to_end(64 * 1024).await {
-                                if let Ok(msg) = bincode::deserialize::<NodeMessage>(&msg_bytes) {
-                                    match msg {
-                                        NodeMessage::NegativeVote { voter_id, reason, metrics } => {
-                                            println!("Leader received negative vote from Node {} due to {:?}", voter_id, reason);
-                                            self.negative_votes_received.insert(voter_id, reason.clone());
-                                            self.update_candidate(voter_id, metrics);
-                                            
-                                            if self.negative_votes_received.len() >= 2 {
-                                                println!("Received enough negative votes, stepping down");
-                                                self.state = State::DefactoLeader;
-                                                return;
-                                            }
-                                        }
-                                        NodeMessage::UpdateMetrics(new_metrics) => {
-                                            self.metrics = new_metrics;
-                                            println!("Updated leader metrics: CPU: {:.1}%, Memory: {:.1}%", 
-                                                self.metrics.cpu_load, self.metrics.memory_usage);
+            match timeout(Duration::from_secs(1), self.incoming.recv()).await {
+                Ok(Some(conn)) => {
+                    if let Ok((send, mut recv)) = conn.accept_bi().await {
+                        if let Ok(msg_bytes) = recv.read_to_end(64 * 1024).await {
+                            if let Ok(msg) = bincode::deserialize::<NodeMessage>(&msg_bytes) {
+                                match msg {
+                                    NodeMessage::NegativeVote { voter_id, reason, metrics } => {
+                                        println!("Leader received negative vote from Node {} due to {:?}", voter_id, reason);
+                                        self.negative_votes_received.insert(voter_id, reason.clone());
+                                        self.update_candidate(voter_id, metrics);
+                                        
+                                        if self.negative_votes_received.len() >= 2 {
+                                            println!("Received enough negative votes, stepping down");
+                                            self.state = State::DefactoLeader;
+                                            return;
                                         }
-                                        _ => {}
                                     }
+                                    NodeMessage::UpdateMetrics(new_metrics) => {
+                                        self.metrics = new_metrics;
+                                        println!("Updated leader metrics: CPU: {:.1}%, Memory: {:.1}%", 
+                                            self.metrics.cpu_load, self.metrics.memory_usage);
+                                    }
+                                    _ => {}
                                 }
                             }
                         }
@@ -211,52 +163,50 @@ impl Node {
             }
             //println!("Listening!!!");
             
-            match timeout(Duration::from_millis(100), self.server_endpoint.accept()).await {
-                Ok(Some(incoming)) => {
-                    if let Ok(conn) = incoming.await {
-                        println!("{}", conn.remote_address());
-                        if let Ok((send, mut recv)) = conn.accept_bi().await {
-                           //println!("Connection Accepted");
+            match timeout(Duration::from_millis(100), self.incoming.recv()).await {
+                Ok(Some(conn)) => {
+                    println!("{}", conn.remote_address());
+                    if let Ok((send, mut recv)) = conn.accept_bi().await {
+                       //println!("Connection Accepted");
+                        
+                        if let Ok(msg_bytes) = recv.read_to_end(64 * 1024).await {
+                           // println!("Message Received");
+                            //println!("Message length: {}", msg_bytes.len());
                             
-                            if let Ok(msg_bytes) = recv.read_to_end(64 * 1024).await {
-                               // println!("Message Received");
-                                //println!("Message length: {}", msg_bytes.len());
-                                
-                                if let Ok(msg) = bincode::deserialize::<NodeMessage>(&msg_bytes) {
-                                   // println!("Begin Message Decoding");
-                                    match msg {
-                                        NodeMessage::Heartbeat { leader_id, metrics: leader_metrics, candidates } => {
-                                            println!("Node {} received heartbeat from leader {}", self.id, leader_id);
-                                            self.last_heartbeat = Instant::now();
-                                            self.current_leader_id = Some(leader_id);
-                                            CURRENT_LEADER_ID.store(leader_id, AtomicOrdering::SeqCst);
-                                            self.candidates = candidates;
-                                            
-                                            if let Some(reason) = self.should_cast_negative_vote(&leader_metrics) {
-                                                self.send_negative_vote(leader_id, reason).await;
-                                            }
+                            if let Ok(msg) = bincode::deserialize::<NodeMessage>(&msg_bytes) {
+                               // println!("Begin Message Decoding");
+                                match msg {
+                                    NodeMessage::Heartbeat { leader_id, metrics: leader_metrics, candidates } => {
+                                        println!("Node {} received heartbeat from leader {}", self.id, leader_id);
+                                        self.last_heartbeat = Instant::now();
+                                        self.current_leader_id = Some(leader_id);
+                                        CURRENT_LEADER_ID.store(leader_id, AtomicOrdering::SeqCst);
+                                        self.candidates = candidates;
+                                        
+                                        if let Some(reason) = self.should_cast_negative_vote(&leader_metrics) {
+                                            self.send_negative_vote(leader_id, reason).await;
                                         }
-                                        NodeMessage::ElectionResult { new_leader_id } => {
-                                            println!("Node {} received election result: new leader is {}", self.id, new_leader_id);
-                                            CURRENT_LEADER_ID.store(new_leader_id, AtomicOrdering::SeqCst);
-                                            if new_leader_id == self.id {
-                                                self.state = State::Leader;
-                                                return;
-                                            } else {
-                                                self.current_leader_id = Some(new_leader_id);
-                                            }
+                                    }
+                                    NodeMessage::ElectionResult { new_leader_id } => {
+                                        println!("Node {} received election result: new leader is {}", self.id, new_leader_id);
+                                        CURRENT_LEADER_ID.store(new_leader_id, AtomicOrdering::SeqCst);
+                                        if new_leader_id == self.id {
+                                            self.state = State::Leader;
+                                            return;
+                                        } else {
+                                            self.current_leader_id = Some(new_leader_id);
                                         }
-                                        _ => {}
                                     }
-                                } else {
-                                    println!("MESSAGE NEVER DECODED");
+                                    _ => {}
                                 }
                             } else {
-                                println!("MESSAGE NEVER RECEIVED");
+                                println!("MESSAGE NEVER DECODED");
                             }
                         } else {
-                            println!("CONNECTION NEVER ACCEPTED");
+                            println!("MESSAGE NEVER RECEIVED");
                         }
+                    } else {
+                        println!("CONNECTION NEVER ACCEPTED");
                     }
                 }
                 Ok(None) => {
@@ -427,7 +377,7 @@ impl Node {
                 let result: Result<(), anyhow::Error> = async {
                     let conn = client_endpoint.connect(
                         peer_addr,
-                        "localhost",
+                        &server_name(&peer_addr),
                     )?
                     .await?;
                     //println!("[client] connected: addr={}", conn.remote_address());
@@ -485,10 +435,3 @@ impl Node {
         }
     }
 }
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Candidate {
-    pub id: u64,
-    pub metrics: SystemMetrics,
-    pub score: f64,
-}
@@ -1,27 +1,24 @@
-use quinn::{Endpoint, ServerConfig, TransportConfig, ClientConfig};
-use rand::seq::index;
-use rustls::server;
 use std::error::Error;
 use std::net::SocketAddr;
-use remote_trait_object::{Context, Service, ServiceToExport, Config};
+use remote_trait_object::{Context, ServiceToExport, Config};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
 
-mod transport;
-mod image_steganographer;
-mod quinn_utils;
 mod cloud_leader_election;
-use image_steganographer::{ImageSteganographer, SomeImageSteganographer};
-use image;
-use transport::{create, TransportEnds};
-use quinn_utils::*;
-use quinn_proto::crypto::rustls::QuicClientConfig;
-use cloud_leader_election::{State, VoteReason, SystemMetrics, Node};
-use futures::{FutureExt, StreamExt};
+use common::image_steganographer::{ImageSteganographer, SomeImageSteganographer};
+use common::transport::{accept, TransportEnds};
+use common::quinn_utils::*;
+use common::mux::{Router, ALPN_ELECTION, ALPN_QUIC_HTTP, ALPN_STEG};
+use common::http09;
+use common::users::UserDirectory;
+use common::carriers::CarrierLibrary;
+use common::access::AccessControl;
+use cloud_leader_election::Node;
 use tokio::time::{timeout, Duration};
-use tokio::task::spawn_blocking;
 use tokio::sync::{Mutex, Semaphore};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
 
 pub static CURRENT_LEADER_ID: AtomicU64 = AtomicU64::new(0);
 pub static PERSONAL_ID: AtomicU64 = AtomicU64::new(0);
@@ -29,155 +26,119 @@ pub static PERSONAL_ID: AtomicU64 = AtomicU64::new(0);
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
 
-    // Setup Quinn endpoints for Node
-    let server_addr_leader_election: SocketAddr = "10.7.19.117:5016".parse()?;
+    // Leader election, the steganographer service and the optional file server all share
+    // one QUIC endpoint and are told apart by ALPN.
+    let server_addr: SocketAddr = "10.7.19.117:5017".parse()?;
     let peer_servers_leader_election: Vec<SocketAddr> = vec![
-        "10.7.16.154:5016".parse()?,
-        "10.7.16.71:5016".parse()?,
-    ];
-
-    // Setup Quinn endpoints for steganographer
-    let server_addrs: Vec<SocketAddr> = vec![
-        "10.7.19.117:5017".parse()?,
+        "10.7.16.154:5017".parse()?,
+        "10.7.16.71:5017".parse()?,
     ];
 
     println!("Quin node is beginning setup");
     let my_id = 2; // Make sure this matches your node ID
     PERSONAL_ID.store(my_id as u64, AtomicOrdering::Relaxed);
 
-    let mut quinn_node = Node::new(my_id, server_addr_leader_election, peer_servers_leader_election).await?;
+    let tls = TlsConfig::from_env();
+    let users = Arc::new(UserDirectory::from_env(&tls).map_err(|e| e.to_string())?);
+    let carriers = Arc::new(CarrierLibrary::from_env().map_err(|e| e.to_string())?);
+    println!("Loaded {} carrier images", carriers.carriers().len());
+    let access = AccessControl::from_env(&tls).map_err(|e| e.to_string())?.map(Arc::new);
+    if access.is_none() {
+        println!("No cluster key found, access policies and provenance signatures are disabled");
+    }
+    // Threads all sessions together may embed and extract with; by default every core
+    let steg_pool = match env::var("P2P_STEG_THREADS").ok().and_then(|v| v.parse::<usize>().ok()) {
+        Some(threads) => Some(Arc::new(rayon::ThreadPoolBuilder::new().num_threads(threads.max(1)).build().map_err(|e| e.to_string())?)),
+        None => None,
+    };
+
+    let mut router = Router::new();
+    // Users hold certificates from the cluster CA too; only nodes may take part in elections.
+    // Other modes cannot tell a node from anyone else, so the node leads on its own there.
+    let election_incoming = match tls {
+        TlsConfig::Cluster { .. } => Some(router.route_only(ALPN_ELECTION, peer_is_node)),
+        _ => None,
+    };
+    let mut steg_incoming = router.route(ALPN_STEG);
+    // Serving files over HTTP/0.9 is opt-in
+    if let Ok(root) = env::var("P2P_HTTP_ROOT") {
+        let root: Arc<Path> = Arc::from(PathBuf::from(root));
+        let mut http_incoming = router.route(ALPN_QUIC_HTTP);
+        tokio::spawn(async move {
+            while let Some(conn) = http_incoming.recv().await {
+                tokio::spawn(http09::handle_connection(root.clone(), conn));
+            }
+        });
+    }
+    let (server_endpoint, _cert) = make_server_endpoint(server_addr, &tls, &router.protocols()).map_err(|e| e.to_string())?;
+    tokio::spawn(router.serve(server_endpoint));
+    println!("Node endpoint is listening on {}", server_addr);
+
+    let mut quinn_node = match election_incoming {
+        Some(incoming) => Some(Node::new(my_id, incoming, peer_servers_leader_election, &tls).await?),
+        None => {
+            println!("Leader election needs cluster certificates, this node leads on its own");
+            CURRENT_LEADER_ID.store(my_id, AtomicOrdering::SeqCst);
+            None
+        }
+    };
     // Spawn the Node task
     let node_handle = tokio::spawn(async move {
-        quinn_node.run().await;
+        if let Some(quinn_node) = &mut quinn_node {
+            quinn_node.run().await;
+        }
         tokio::signal::ctrl_c().await.map_err(|e| Box::new(e) as Box<dyn Error + Send>)?;
         println!("Shutting down node...");
         Ok::<(), Box<dyn Error + Send>>(())
     });
 
-    
-    let server_endpoints = Arc::new({
-        let mut endpoints = Vec::new();
-        for addr in server_addrs {
-            let (endpoint, _cert) = make_server_endpoint(addr).unwrap();
-            endpoints.push(endpoint);
-        }
-        endpoints
-    });
-    let server_endpoints_clone = Arc::clone(&server_endpoints);
-    println!("Steganography service endpoints are setup");
-
     let transport_ends_vec = Arc::new(Mutex::new(Vec::new()));
     let transport_ends_vec_clone = Arc::clone(&transport_ends_vec);
 
     // Limit the number of concurrent connections
     let max_connections = 10;
     let semaphore = Arc::new(Semaphore::new(max_connections));
-    let request_queue = Arc::new(Mutex::new(VecDeque::new()));
 
     let connection_handle = tokio::spawn(async move {
         loop {
-            let server_endpoints_clone = Arc::clone(&server_endpoints_clone); // Clone the Arc for use within this iteration
             // Check if this node is the current leader
             if CURRENT_LEADER_ID.load(AtomicOrdering::SeqCst) != my_id {
                 tokio::time::sleep(Duration::from_secs(1)).await;
                 continue;
             }
-    
-            for endpoint in server_endpoints_clone.iter() {
-                match semaphore.try_acquire() {
-                    Ok(_) => {
-                        // Acquired a connection slot
-                        let semaphore_clone = semaphore.clone();
-                        let transport_ends_vec_clone = Arc::clone(&transport_ends_vec_clone);
-                        let endpoint = endpoint.clone(); // Clone the endpoint to move into the task
-                        tokio::spawn(async move {
-                            match timeout(Duration::from_secs(1000), endpoint.accept()).await {
-                                Ok(Some(incoming)) => {
-                                    println!("Received a connection request from client");
-                                    match incoming.await {
-                                        Ok(conn) => {
-                                            let ends = match create(conn).await {
-                                                Ok(ends) => ends,
-                                                Err(e) => {
-                                                    eprintln!("Failed to create transport ends: {}", e);
-                                                    return;
-                                                }
-                                            };
-                                            let mut vec = transport_ends_vec_clone.lock().await;
-                                            vec.push(ends);
-                                        }
-                                        Err(e) => {
-                                            eprintln!("Failed to accept incoming connection: {}", e);
-                                        }
-                                    }
-                                }
-                                Ok(None) => {
-                                    println!("Server endpoint has stopped accepting new connections");
-                                }
-                                Err(e) => {
-                                    eprintln!("Error accepting incoming connection: {}", e);
-                                }
-                            }
-                            drop(semaphore_clone); // Release the semaphore when done
-                        });
-                    }
-                    Err(_) => {
-                        println!("Max connections reached, queuing the request...");
-                        let mut queue = request_queue.lock().await;
-                        queue.push_back(endpoint.clone());
-                        tokio::time::sleep(Duration::from_secs(1)).await;
-                        continue;
-                    }
-                }
-            }
 
-            {
-                let mut queue = request_queue.lock().await;
-                while let Some(endpoint) = queue.pop_front() {
-                    match semaphore.try_acquire() {
-                        Ok(_) => {
-                            let semaphore_clone = semaphore.clone();
-                            let transport_ends_vec_clone = Arc::clone(&transport_ends_vec_clone);
-                            tokio::spawn(async move {
-                                match timeout(Duration::from_secs(1000), endpoint.accept()).await {
-                                    Ok(Some(incoming)) => {
-                                        println!("Received a connection request from client");
-                                        match incoming.await {
-                                            Ok(conn) => {
-                                                let ends = match create(conn).await {
-                                                    Ok(ends) => ends,
-                                                    Err(e) => {
-                                                        eprintln!("Failed to create transport ends: {}", e);
-                                                        return;
-                                                    }
-                                                };
-                                                let mut vec = transport_ends_vec_clone.lock().await;
-                                                vec.push(ends);
-                                            }
-                                            Err(e) => {
-                                                eprintln!("Failed to accept incoming connection: {}", e);
-                                            }
-                                        }
-                                    }
-                                    Ok(None) => {
-                                        println!("Server endpoint has stopped accepting new connections");
-                                    }
-                                    Err(e) => {
-                                        eprintln!("Error accepting incoming connection: {}", e);
-                                    }
-                                }
-                                drop(semaphore_clone); // Release the semaphore when done
-                            });
-                        }
-                        Err(_) => {
-                            queue.push_front(endpoint);
-                            break;
+            // Wait for a free connection slot; further clients queue up in the router
+            let permit = match semaphore.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            match timeout(Duration::from_secs(1), steg_incoming.recv()).await {
+                Ok(Some(conn)) => {
+                    println!("Received a connection request from client");
+                    let transport_ends_vec_clone = Arc::clone(&transport_ends_vec_clone);
+                    tokio::spawn(async move {
+                        match accept(conn).await {
+                            Ok(ends) => {
+                                let mut vec = transport_ends_vec_clone.lock().await;
+                                vec.push(ends);
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to create transport ends: {}", e);
+                            }
                         }
-                    }
+                        drop(permit); // Release the semaphore when done
+                    });
+                }
+                Ok(None) => {
+                    println!("Server endpoint has stopped accepting new connections");
+                    break;
+                }
+                Err(_) => {
+                    // No client within the timeout, check leadership and Ctrl+C again
                 }
             }
-    
-            let ctrl_c_timeout = Duration::from_secs(1);
+
+            let ctrl_c_timeout = Duration::from_millis(10);
             match timeout(ctrl_c_timeout, tokio::signal::ctrl_c()).await {
                 Ok(Ok(())) => {
                     break;
@@ -189,15 +150,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     // Timeout occurred, continue the loop
                 }
             }
-            tokio::time::sleep(Duration::from_secs(1)).await;
         }
-    
+
         println!("Shutting down connection handle...");
         Ok::<(), String>(())
     });
-    
-    
-
 
     let contexts: Arc<Mutex<HashMap<TransportEnds, Context>>> = Arc::new(Mutex::new(HashMap::new()));
 
@@ -217,14 +174,30 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     
                     // Only create and export the service if this node is the leader and the context doesn’t already exist
                     if !contexts.contains_key(ends) {
+                        let session = match users.session(ends.peer_identity()) {
+                            Ok(session) => session,
+                            Err(e) => {
+                                println!("Refusing client {:?}: {}", ends.get_remote_address(), e);
+                                ends.close("unknown user");
+                                return false;
+                            }
+                        };
+                        let user = session.user().to_string();
+                        let mut steganographer = SomeImageSteganographer::new(75, 10).with_session(session).with_carriers(carriers.clone());
+                        if let Some(access) = &access {
+                            steganographer = steganographer.with_access_control(access.clone());
+                        }
+                        if let Some(pool) = &steg_pool {
+                            steganographer = steganographer.with_pool(pool.clone());
+                        }
                         let context = Context::with_initial_service_export(
                             Config::default_setup(),
                             ends.send.clone(),
                             ends.recv.clone(),
-                            ServiceToExport::new(Box::new(SomeImageSteganographer::new(75, 10)) as Box<dyn ImageSteganographer>),
+                            ServiceToExport::new(Box::new(steganographer) as Box<dyn ImageSteganographer>),
                         );
                         contexts.insert(ends.clone(), context);
-                        println!("Steganographer service started for client {:?}", ends.get_remote_address());
+                        println!("Steganographer service started for client {:?} ({})", ends.get_remote_address(), user);
                     }
                     
                     true
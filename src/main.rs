@@ -4,12 +4,11 @@ use std::net::SocketAddr;
 use remote_trait_object::{Context, Service, ServiceToExport, Config};
 use std::sync::Arc;
 
-mod transport;
-mod image_steganographer;
-mod quinn_utils;
-use image_steganographer::{ImageSteganographer, SomeImageSteganographer};
-use transport::{create, TransportEnds};
-use quinn_utils::*;
+use common::image_steganographer::{ImageSteganographer, SomeImageSteganographer};
+use common::transport::{accept, connect};
+use common::quinn_utils::*;
+use common::mux::ALPN_STEG;
+use common::carriers::CarrierLibrary;
 use quinn_proto::crypto::rustls::QuicClientConfig;
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -17,26 +16,31 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let server_addr: SocketAddr = "0.0.0.0:5000".parse()?;
     let client_addr: SocketAddr = "127.0.0.1:0".parse()?;
 
-    let (server_endpoint, _server_cert) = make_server_endpoint(server_addr).unwrap();
+    let tls = TlsConfig::from_env();
+    let (server_endpoint, _server_cert) = make_server_endpoint(server_addr, &tls, &[ALPN_STEG]).unwrap();
     
     
     let mut client_endpoint = quinn::Endpoint::client(client_addr)?;
-    client_endpoint.set_default_client_config(ClientConfig::new(Arc::new(QuicClientConfig::try_from(
-        rustls::ClientConfig::builder()
-            .dangerous()
-            .with_custom_certificate_verifier(SkipServerVerification::new())
-            .with_no_client_auth(),
-    )?)));
+    client_endpoint.set_default_client_config(configure_client_tls(&tls, ALPN_STEG).map_err(|e| e.to_string())?);
 
     // Create transport ends
-    let transport_ends = create(server_endpoint, client_endpoint).await?;
+    let server_side = async {
+        let incoming = server_endpoint.accept().await.ok_or("server endpoint closed")?;
+        let conn = incoming.await.map_err(|e| e.to_string())?;
+        accept(conn).await
+    };
+    let (server_ends, _client_ends) = tokio::join!(
+        server_side,
+        connect(client_endpoint, "127.0.0.1:5000".parse()?),
+    );
+    let server_ends = server_ends?;
 
     // Create RTO context
     let _context_steganographer = Context::with_initial_service_export(
         Config::default_setup(),
-        transport_ends.send1,
-        transport_ends.recv1,
-        ServiceToExport::new(Box::new(SomeImageSteganographer::new(75,10)) as Box<dyn ImageSteganographer>),
+        server_ends.send,
+        server_ends.recv,
+        ServiceToExport::new(Box::new(SomeImageSteganographer::new(75,10).with_carriers(Arc::new(CarrierLibrary::from_env().map_err(|e| e.to_string())?))) as Box<dyn ImageSteganographer>),
     );
     
     // Create and register the steganographer service